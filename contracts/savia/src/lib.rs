@@ -1,5 +1,6 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, xdr::ToXdr, Address, Bytes, BytesN, Env, String, Vec, Map};
+#![allow(clippy::too_many_arguments)]
+use soroban_sdk::{contract, contractclient, contracterror, contractimpl, contracttype, symbol_short, token, xdr::ToXdr, Address, Bytes, BytesN, Env, String, Symbol, Vec, Map};
 
 // Enhanced Savia Smart Contracts for Stellar - Mexican Compliance Version
 // Implements SEP-24 KYC, medical documentation, and dynamic NFT system
@@ -27,8 +28,14 @@ pub struct Campaign {
     pub last_proof_submitted: u64,
     pub proof_deadline: u64,
     pub funds_locked: bool,
+    pub lock_reason: LockReason, // Why funds are locked; only proof-deadline locks clear on new proof
     pub etherfuse_account: String,
-    pub peso_exchange_rate: u64, // Rate per 1 XLM in Mexican pesos (scaled by 10000)
+    pub peso_exchange_rate: u64, // Rate per 1 XLM in Mexican pesos at creation (scaled by 10000)
+    pub peso_raised: u64, // Sum of donation peso amounts, each at its own rate
+    pub released_amount: u64,
+    pub payout_amount: u64, // Released funds sent to payouts that have not failed
+    pub refunding: bool,
+    pub refund_started_at: u64,
 }
 
 #[derive(Clone)]
@@ -39,30 +46,54 @@ pub struct Donation {
     pub donor: Address,
     pub amount: u64,
     pub peso_amount: u64,
+    pub gross_peso_amount: u64, // Peso value given, platform fee included
+    pub exchange_rate: u64, // Rate applied to this donation (scaled by 10000)
     pub timestamp: u64,
     pub nft_minted: bool,
     pub anonymous: bool,
     pub refunded: bool,
-    pub etherfuse_tx_id: String,
+    pub etherfuse_tx_id: Option<BytesN<32>>, // Conversion record, None for donations recorded without one
 }
 
+// Public view of a donation; the donor is hidden when the donation is anonymous
+#[derive(Clone)]
+#[contracttype]
+pub struct DonationListing {
+    pub id: BytesN<32>,
+    pub campaign_id: BytesN<32>,
+    pub donor: Option<Address>,
+    pub amount: u64,
+    pub peso_amount: u64,
+    pub exchange_rate: u64,
+    pub timestamp: u64,
+    pub anonymous: bool,
+    pub refunded: bool,
+}
+
+// Personal data is held only as salted SHA-256 commitments, sha256(field || salt || value),
+// each field under its own random salt; the holder reveals a field to a verifier,
+// who checks it through `prove_kyc_field`
 #[derive(Clone)]
 #[contracttype]
 pub struct KYCRecord {
     pub entity: Address,
-    pub curp: String,
-    pub full_name: String,
-    pub phone_number: String,
-    pub email: String,
-    pub address: String,
+    pub curp_hash: BytesN<32>,
+    pub full_name_hash: BytesN<32>,
+    pub phone_hash: BytesN<32>, // Over the canonical 10-digit number
+    pub email_hash: BytesN<32>,
+    pub address_hash: BytesN<32>,
+    pub proven_fields: Vec<KYCField>, // Fields a verifier has checked against their commitments
+    pub revealed_salts: Map<BytesN<32>, KYCField>, // Salts published by proofs, with the field each opened
     pub verification_level: KYCLevel,
+    pub submitted_at: u64,
+    pub verified_by: Option<Address>, // KYC verifier that attested the current level
     pub verified_at: u64,
-    pub expires_at: u64,
-    pub medical_license: Option<String>, // For medical professionals
-    pub institution: Option<String>,
+    pub expires_at: u64, // Zero until a verifier attests the record
+    pub medical_license: Option<String>, // Cédula profesional, already public in the SEP registry
+    pub institution: Option<String>,     // Employer or hospital, not personal data
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 #[contracttype]
 pub enum KYCLevel {
     Unverified,
@@ -85,7 +116,7 @@ pub struct MedicalDocumentation {
     pub notes: String,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 #[contracttype]
 pub enum MedicalDocType {
     MedicalDiagnosis,
@@ -98,7 +129,7 @@ pub enum MedicalDocType {
     InsuranceClaimDenial,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 #[contracttype]
 pub enum DocumentStatus {
     Pending,
@@ -107,6 +138,121 @@ pub enum DocumentStatus {
     Expired,
 }
 
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum LockReason {
+    Unlocked,
+    ProofDeadline,
+    EmergencyPause,
+    KYCLapsed,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct Milestone {
+    pub amount: u64,
+    pub required_docs: Vec<MedicalDocType>,
+    pub linked_docs: Vec<BytesN<32>>,
+    pub released: bool,
+    pub released_at: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum KYCField {
+    CURP,
+    FullName,
+    Phone,
+    Email,
+    Address,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum KYCAction {
+    Verified,
+    Renewed,
+    Revoked,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum KYCReason {
+    InitialReview,
+    PeriodicReview,
+    DocumentsUpdated,
+    DocumentsExpired,
+    IdentityMismatch,
+    FraudSuspected,
+    HolderRequest,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct KYCHistoryEntry {
+    pub action: KYCAction,
+    pub reason: KYCReason,
+    pub level: KYCLevel, // Level after the action
+    pub verifier: Address,
+    pub timestamp: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum PayoutStatus {
+    Requested,
+    Settled,
+    Failed,
+}
+
+// Off-ramp of released funds to the beneficiary's bank account
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct Payout {
+    pub id: u64,
+    pub campaign_id: BytesN<32>,
+    pub beneficiary: Address,
+    pub clabe: String,
+    pub amount: u64,
+    pub peso_amount: u64, // Estimated at request time
+    pub exchange_rate: u64,
+    pub status: PayoutStatus,
+    pub external_id: String, // Anchor reference, empty until settled or failed
+    pub requested_at: u64,
+    pub settled_at: u64,
+}
+
+// Every field is optional; unset fields match any campaign
+#[derive(Clone)]
+#[contracttype]
+pub struct CampaignFilter {
+    pub category: Option<String>,
+    pub location: Option<String>,
+    pub verified: Option<bool>,
+    pub active: Option<bool>, // Active campaigns are before their end time and not refunding
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct CampaignPage {
+    pub campaigns: Vec<Campaign>,
+    pub next_cursor: Option<u64>, // Pass back as `cursor` to continue, None when done
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct RefundPool {
+    pub escrow_at_start: u64,  // Escrow left when refunds began
+    pub raised_at_start: u64,  // Net amount raised when refunds began
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct FeeAccount {
+    pub collected: u64,
+    pub withdrawn: u64,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct DynamicNFT {
@@ -155,1147 +301,5725 @@ pub struct TrustScore {
 #[contracttype]
 pub struct EtherFuseTransaction {
     pub id: BytesN<32>,
+    pub donation_id: BytesN<32>,
     pub campaign_id: BytesN<32>,
     pub donor: Address,
     pub peso_amount: u64,
     pub xlm_amount: u64,
     pub exchange_rate: u64,
-    pub etherfuse_tx_id: String,
+    pub etherfuse_tx_id: String, // External EtherFuse reference, empty until the anchor reports back
     pub status: TransactionStatus,
     pub timestamp: u64,
+    pub settled_at: u64,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 #[contracttype]
-pub enum TransactionStatus {
-    Pending,
-    Confirmed,
-    Failed,
-    Refunded,
+pub struct RateReport {
+    pub rate: u64, // Pesos per 1 XLM (scaled by 10000)
+    pub timestamp: u64,
 }
 
-// ========== ENHANCED STORAGE KEYS ==========
-
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Debug)]
 #[contracttype]
-pub enum DataKey {
-    Campaign(BytesN<32>),
-    Donation(BytesN<32>),
-    KYCRecord(Address),
-    MedicalDoc(BytesN<32>),
-    DynamicNFT(BytesN<32>),
-    TrustScore(Address),
-    EtherFuseTransaction(BytesN<32>),
-    PlatformFee,
-    CampaignCounter,
-    DonationCounter,
-    NFTCounter,
-    MedicalDocCounter,
-    EtherFuseConfig,
-    PesoExchangeRate,
-    KYCVerifiers,
-    MedicalVerifiers,
-    OwnerNFTs(Address),
+pub struct OracleConfig {
+    pub min_reports: u32,        // Fresh reports needed before the rate moves
+    pub max_deviation_bps: u32,  // Largest accepted move from the last rate
+    pub staleness_window: u64,   // Seconds a rate or report stays usable
 }
 
-// ========== ENHANCED ERROR CODES ==========
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum LimitUnit {
+    Pesos,
+    Uma, // Unidad de Medida y Actualización, as used by LFPIORPI thresholds
+}
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+// Thresholds are in `unit`; pesos use the same scale as Donation::peso_amount
+#[derive(Clone, PartialEq, Debug)]
 #[contracttype]
-pub enum SaviaError {
-    InvalidFee = 1,
-    InvalidGoal = 2,
-    InvalidDuration = 3,
-    CampaignNotFound = 4,
-    CampaignEnded = 5,
-    InvalidAmount = 6,
-    ScoreExists = 7,
-    InsufficientFunds = 8,
-    NotApproved = 9,
-    KYCNotVerified = 10,
-    InvalidCURP = 11,
-    InvalidPhoneNumber = 12,
-    MedicalDocsExpired = 13,
-    ProofDeadlineExceeded = 14,
-    FundsLocked = 15,
-    EtherFuseError = 16,
-    InvalidMedicalDoc = 17,
-    NotAuthorized = 18,
-    DocumentExpired = 19,
-    RefundPeriodExpired = 20,
+pub struct ComplianceConfig {
+    pub unit: LimitUnit,
+    pub uma_value: u64, // Pesos per UMA
+    pub verified_donor_threshold: u64, // Donations above this need a BasicVerified donor, 0 = none
+    pub period: u64, // Seconds in a cumulative-limit period
 }
 
-// ========== ENHANCED MAIN CONTRACT ==========
+// Caps for one KYC tier, in the configured unit; 0 means no cap
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct TierLimits {
+    pub max_donation: u64,
+    pub max_period_total: u64, // Per donor, per period
+    pub max_campaign_goal: u64,
+}
 
-#[contract]
-pub struct SaviaContract;
+// Cumulative donations from one donor to one campaign that call for an aviso
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct AmlConfig {
+    pub threshold: u64, // In the compliance config unit, 0 = disabled
+    pub window: u64,    // Seconds of donations counted towards the total
+}
 
-#[contractimpl]
-impl SaviaContract {
-    
-    /// Initialize the contract with enhanced Mexican compliance
-    pub fn initialize(
-        env: Env,
-        platform_fee: u64,
-        etherfuse_config: String,
-        initial_peso_rate: u64,
-    ) -> Result<(), soroban_sdk::Error> {
-        if platform_fee > 1000 {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::InvalidFee as u32));
-        }
-        
-        env.storage().instance().set(&DataKey::PlatformFee, &platform_fee);
-        env.storage().instance().set(&DataKey::CampaignCounter, &0u64);
-        env.storage().instance().set(&DataKey::DonationCounter, &0u64);
-        env.storage().instance().set(&DataKey::NFTCounter, &0u64);
-        env.storage().instance().set(&DataKey::MedicalDocCounter, &0u64);
-        env.storage().instance().set(&DataKey::EtherFuseConfig, &etherfuse_config);
-        env.storage().instance().set(&DataKey::PesoExchangeRate, &initial_peso_rate);
-        
-        // Initialize verifier lists
-        let empty_vec: Vec<Address> = Vec::new(&env);
-        env.storage().instance().set(&DataKey::KYCVerifiers, &empty_vec);
-        env.storage().instance().set(&DataKey::MedicalVerifiers, &empty_vec);
-        
-        Ok(())
-    }
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct AmlEntry {
+    pub donation_id: BytesN<32>,
+    pub timestamp: u64,
+    pub peso_amount: u64,
+}
 
-    /// Register KYC information with Mexican CURP validation
-    pub fn register_kyc(
-        env: Env,
-        entity: Address,
-        curp: String,
-        full_name: String,
-        phone_number: String,
-        email: String,
-        address: String,
-        medical_license: Option<String>,
-        institution: Option<String>,
-    ) -> Result<(), soroban_sdk::Error> {
-        // Validate CURP format (18 characters)
-        if curp.len() != 18 {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::InvalidCURP as u32));
-        }
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct AmlAlert {
+    pub id: u64,
+    pub donor: Address,
+    pub campaign_id: BytesN<32>,
+    pub beneficiary: Address,
+    pub donation_id: BytesN<32>, // Donation that crossed the threshold
+    pub rolling_total: u64,      // Pesos within the window, including that donation
+    pub threshold: u64,          // Threshold in pesos when the alert was raised
+    pub timestamp: u64,
+}
 
-        // Validate Mexican phone number format (10 digits)
-        if phone_number.len() != 10 {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::InvalidPhoneNumber as u32));
-        }
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct DonorPeriodTotal {
+    pub period_start: u64,
+    pub total: u64, // Pesos given since period_start, platform fee included
+}
 
-        let current_time = env.ledger().timestamp();
-        let expiry_time = current_time + (365 * 24 * 60 * 60); // 1 year validity
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum ScreeningMode {
+    Allowlist, // Only addresses the screening contract lists may interact
+    Denylist,  // Addresses the screening contract lists are refused
+}
 
-        let verification_level = match (&medical_license, &institution) {
-            (Some(_), Some(_)) => KYCLevel::MedicalVerified,
-            _ => KYCLevel::BasicVerified,
-        };
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct ScreeningConfig {
+    pub contract: Address,
+    pub mode: ScreeningMode,
+}
 
-        let kyc_record = KYCRecord {
-            entity: entity.clone(),
-            curp,
-            full_name,
-            phone_number,
-            email,
-            address,
-            verification_level,
-            verified_at: current_time,
-            expires_at: expiry_time,
-            medical_license,
-            institution,
-        };
+// Asset identifier used by SEP-40 price feeds
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
 
-        env.storage().persistent().set(&DataKey::KYCRecord(entity), &kyc_record);
-        Ok(())
-    }
+// Price record returned by SEP-40 price feeds
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
 
-    /// Create a new campaign with enhanced medical requirements
-    pub fn create_campaign(
-        env: Env,
-        beneficiary: Address,
-        title: String,
-        description: String,
-        medical_condition: String,
-        goal_amount: u64,
-        duration_days: u64,
-        category: String,
-        location: String,
-        etherfuse_account: String,
-    ) -> Result<BytesN<32>, soroban_sdk::Error> {
-        // Validate KYC verification
-        let kyc_record: KYCRecord = env.storage().persistent().get(&DataKey::KYCRecord(beneficiary.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::KYCNotVerified as u32))?;
+// Both assets are priced in the feed's base currency (USD on Reflector),
+// so XLM/MXN is the ratio of the two prices
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct PriceFeedConfig {
+    pub feed: Address,
+    pub xlm_asset: Asset,
+    pub mxn_asset: Asset,
+    pub max_age: u64, // Seconds a feed price stays usable
+}
 
-        if kyc_record.expires_at < env.ledger().timestamp() {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::KYCNotVerified as u32));
-        }
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct PesoRateQuote {
+    pub rate: u64, // Pesos per 1 XLM (scaled by 10000)
+    pub updated_at: u64,
+    pub from_price_feed: bool,
+}
 
-        // Validate inputs
-        if goal_amount == 0 {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::InvalidGoal as u32));
-        }
-        
-        if duration_days == 0 || duration_days > 365 {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::InvalidDuration as u32));
-        }
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub enum TransactionStatus {
+    Pending,
+    Confirmed,
+    Failed,
+    Refunded,
+}
 
-        // Get current peso exchange rate
-        let peso_rate: u64 = env.storage().instance().get(&DataKey::PesoExchangeRate).unwrap_or(180000); // Default ~18 pesos per XLM
+impl TransactionStatus {
+    // Pending settles once; confirmed conversions can still be refunded,
+    // failed and refunded ones are final
+    fn can_transition_to(&self, next: &TransactionStatus) -> bool {
+        matches!(
+            (self, next),
+            (TransactionStatus::Pending, TransactionStatus::Confirmed)
+                | (TransactionStatus::Pending, TransactionStatus::Failed)
+                | (TransactionStatus::Pending, TransactionStatus::Refunded)
+                | (TransactionStatus::Confirmed, TransactionStatus::Refunded)
+        )
+    }
+}
 
-        // Get and increment campaign counter
-        let counter: u64 = env.storage().instance().get(&DataKey::CampaignCounter).unwrap_or(0);
-        let new_counter = counter + 1;
-        env.storage().instance().set(&DataKey::CampaignCounter, &new_counter);
+// ========== EVENTS ==========
+// Topics are (area, action, subject), e.g. ("campaign", "created", campaign_id)
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct CampaignCreatedEvent {
+    pub beneficiary: Address,
+    pub goal_amount: u64,
+    pub end_time: u64,
+    pub category: String,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct DonationEvent {
+    pub donation_id: BytesN<32>,
+    pub donor: Option<Address>, // None for anonymous donations
+    pub amount: u64,
+    pub peso_amount: u64,
+    pub platform_fee: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct DocumentSubmittedEvent {
+    pub document_hash: BytesN<32>,
+    pub document_type: MedicalDocType,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct DocumentVerifiedEvent {
+    pub document_hash: BytesN<32>,
+    pub verifier: Address,
+    pub approved: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct KYCRegisteredEvent {
+    pub verification_level: KYCLevel,
+    pub expires_at: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct KYCVerifiedEvent {
+    pub verifier: Address,
+    pub verification_level: KYCLevel,
+    pub expires_at: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct KYCFieldProvenEvent {
+    pub verifier: Address,
+    pub field: KYCField,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct KYCRevokedEvent {
+    pub verifier: Address,
+    pub reason: KYCReason,
+    pub campaigns_locked: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct NFTMintedEvent {
+    pub nft_id: BytesN<32>,
+    pub campaign_id: BytesN<32>,
+    pub growth_stage: TreeGrowthStage,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct NFTGrowthEvent {
+    pub nft_id: BytesN<32>,
+    pub previous_stage: TreeGrowthStage,
+    pub growth_stage: TreeGrowthStage,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct FraudReportedEvent {
+    pub reporter: Address,
+    pub fraud_reports: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct CampaignLockedEvent {
+    pub proof_deadline: u64,
+    pub locked_at: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct MilestoneReleasedEvent {
+    pub milestone_index: u32,
+    pub beneficiary: Address,
+    pub amount: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct PayoutEvent {
+    pub payout_id: u64,
+    pub amount: u64,
+    pub status: PayoutStatus,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct RefundEvent {
+    pub donation_id: BytesN<32>,
+    pub donor: Address,
+    pub amount: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct RefundSweptEvent {
+    pub to: Address,
+    pub amount: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct RateUpdatedEvent {
+    pub rate: u64,
+    pub report_count: u32,
+    pub updated_at: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct ConversionSettledEvent {
+    pub donation_id: BytesN<32>,
+    pub external_id: String,
+    pub reversed_amount: u64, // Tokens returned to the donor when the conversion failed
+}
+
+// The donor is left out of the public event; compliance reads it from the alert
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct AmlAlertEvent {
+    pub alert_id: u64,
+    pub rolling_total: u64,
+    pub threshold: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[contracttype]
+pub struct FeesWithdrawnEvent {
+    pub asset: Address,
+    pub to: Address,
+    pub amount: u64,
+}
+
+// ========== EXTERNAL CONTRACTS ==========
+
+// Subset of the SEP-40 price feed interface (e.g. Reflector)
+#[contractclient(name = "PriceFeedClient")]
+pub trait PriceFeed {
+    fn lastprice(env: Env, asset: Asset) -> Option<PriceData>;
+}
+
+// External sanctions or allowlist screening contract
+#[contractclient(name = "ScreeningClient")]
+pub trait Screening {
+    fn is_listed(env: Env, address: Address) -> bool;
+}
+
+// ========== ENHANCED STORAGE KEYS ==========
+
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    Campaign(BytesN<32>),
+    Donation(BytesN<32>),
+    KYCRecord(Address),
+    KYCHistory(Address),
+    BeneficiaryCampaigns(Address),
+    MedicalDoc(BytesN<32>),
+    DynamicNFT(BytesN<32>),
+    TrustScore(Address),
+    EtherFuseTransaction(BytesN<32>),
+    PlatformFee,
+    CampaignCounter,
+    DonationCounter,
+    NFTCounter,
+    MedicalDocCounter,
+    EtherFuseConfig,
+    PesoExchangeRate,
+    PesoRateUpdatedAt,
+    RateReporters,
+    RateReports,
+    OracleConfig,
+    PriceFeed,
+    KYCVerifiers,
+    MedicalVerifiers,
+    EtherFuseOperators,
+    OwnerNFTs(Address),
+    Admin,
+    DonationToken,
+    CampaignEscrow(BytesN<32>),
+    FeeAccount(Address),
+    Treasury,
+    Milestones(BytesN<32>),
+    Payout(u64),
+    PayoutCounter,
+    CampaignPayouts(BytesN<32>),
+    CampaignDocs(BytesN<32>),
+    RefundPool(BytesN<32>),
+    DonationConversion(BytesN<32>),
+    CampaignConversions(BytesN<32>),
+    EtherFuseCounter,
+    CampaignDonations(BytesN<32>),
+    DonorDonations(Address),
+    CampaignRegistry(u64),
+}
+
+// Keys for the compliance subsystem: limits, AML alerts and screening
+#[derive(Clone)]
+#[contracttype]
+pub enum ComplianceKey {
+    ComplianceOfficers,
+    ComplianceConfig,
+    TierLimits(KYCLevel),
+    DonorPeriodTotal(Address),
+    AmlConfig,
+    AmlActivity(Address, Address), // Donor, beneficiary
+    AmlAlert(u64),
+    AmlAlertCounter,
+    Blocked(Address),
+    Screening,
+}
+
+// ========== ENHANCED ERROR CODES ==========
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum SaviaError {
+    InvalidFee = 1,
+    InvalidGoal = 2,
+    InvalidDuration = 3,
+    CampaignNotFound = 4,
+    CampaignEnded = 5,
+    InvalidAmount = 6,
+    ScoreExists = 7,
+    InsufficientFunds = 8,
+    NotApproved = 9,
+    KYCNotVerified = 10,
+    InvalidCURP = 11,
+    InvalidPhoneNumber = 12,
+    MedicalDocsExpired = 13,
+    ProofDeadlineExceeded = 14,
+    FundsLocked = 15,
+    EtherFuseError = 16,
+    InvalidMedicalDoc = 17,
+    NotAuthorized = 18,
+    DocumentExpired = 19,
+    RefundPeriodExpired = 20,
+    AlreadyInitialized = 21,
+    MilestoneNotFound = 22,
+    MilestoneAlreadyReleased = 23,
+    MilestoneDocsNotVerified = 24,
+    RefundNotAvailable = 25,
+    NotInitialized = 26,
+    DonationNotFound = 27,
+    DocumentNotFound = 28,
+    KYCExpired = 29,
+    TrustScoreNotFound = 30,
+    InsufficientTrustScore = 31,
+    InvalidExchangeRate = 32,
+    RateDeviationTooLarge = 33,
+    StaleExchangeRate = 34,
+    SlippageExceeded = 35,
+    TransactionNotFound = 36,
+    InvalidTransactionStatus = 37,
+    InvalidCLABE = 38,
+    PayoutNotFound = 39,
+    InvalidPayoutStatus = 40,
+    KYCNotFound = 41,
+    InvalidKYCLevel = 42,
+    KYCProofMismatch = 43,
+    DonationLimitExceeded = 44,
+    CampaignGoalLimitExceeded = 45,
+    AddressBlocked = 46,
+    KYCFieldsNotProven = 47,
+    KYCSaltReused = 48,
+    MilestonePlanLocked = 49,
+    RefundWindowOpen = 50,
+}
+
+// Longest personal-data field that can be revealed against a KYC commitment
+const MAX_KYC_FIELD_LEN: usize = 256;
+
+// Upper bound on the number of entries returned by a listing call
+const MAX_PAGE_SIZE: u32 = 50;
+
+// Upper bound on the number of registry entries a filtered listing inspects
+const MAX_SCAN_SIZE: u64 = 200;
+
+// ========== MEXICAN IDENTIFIERS ==========
+
+// CLABE: 3-digit bank, 3-digit city, 11-digit account and a check digit
+// computed from the first 17 digits with weights 3, 7, 1
+fn is_valid_clabe(clabe: &String) -> bool {
+    if clabe.len() != 18 {
+        return false;
+    }
+    let mut digits = [0u8; 18];
+    clabe.copy_into_slice(&mut digits);
+    if !digits.iter().all(|d| d.is_ascii_digit()) {
+        return false;
+    }
+
+    const WEIGHTS: [u32; 3] = [3, 7, 1];
+    let sum: u32 = digits[..17]
+        .iter()
+        .enumerate()
+        .map(|(i, d)| ((d - b'0') as u32 * WEIGHTS[i % 3]) % 10)
+        .sum();
+    (10 - sum % 10) % 10 == (digits[17] - b'0') as u32
+}
+
+// Reduce a Mexican phone number to its 10-digit national form. Accepts the
+// bare number, the +52 / 52 country code and the legacy 044 / 045 mobile
+// prefixes; anything other than digits (after a leading +) is rejected.
+fn normalize_mx_phone(env: &Env, phone: &String) -> Option<String> {
+    let len = phone.len() as usize;
+    if !(10..=13).contains(&len) {
+        return None;
+    }
+    let mut buf = [0u8; 13];
+    phone.copy_into_slice(&mut buf[..len]);
+    let raw = &buf[..len];
+
+    let number = match raw {
+        [b'+', b'5', b'2', rest @ ..] => rest,
+        [b'0', b'4', b'4' | b'5', rest @ ..] => rest,
+        [b'5', b'2', rest @ ..] if len == 12 => rest,
+        _ => raw,
+    };
+    if number.len() != 10 || !number.iter().all(|d| d.is_ascii_digit()) {
+        return None;
+    }
+
+    // Area codes never start with 0 or 1. Guadalajara (33), Mexico City
+    // (55, 56) and Monterrey (81) use 2 digits, everywhere else uses 3, and
+    // the local number that follows never starts with 0.
+    if number[0] < b'2' {
+        return None;
+    }
+    let area_code_len = match &number[..2] {
+        b"33" | b"55" | b"56" | b"81" => 2,
+        _ => 3,
+    };
+    if number[area_code_len] == b'0' {
+        return None;
+    }
+
+    Some(String::from_bytes(env, number))
+}
+
+// CURP state codes, plus NE for people born abroad
+const CURP_STATES: [&[u8; 2]; 33] = [
+    b"AS", b"BC", b"BS", b"CC", b"CL", b"CM", b"CS", b"CH", b"DF", b"DG", b"GT",
+    b"GR", b"HG", b"JC", b"MC", b"MN", b"MS", b"NT", b"NL", b"OC", b"PL", b"QT",
+    b"QR", b"SP", b"SL", b"SR", b"TC", b"TS", b"TL", b"VZ", b"YN", b"ZS", b"NE",
+];
+
+// Value of a CURP character in the check-digit dictionary
+// "0123456789ABCDEFGHIJKLMNÑOPQRSTUVWXYZ"; Ñ never appears (RENAPO writes X)
+// but still shifts the letters after N
+fn curp_char_value(c: u8) -> u32 {
+    match c {
+        b'0'..=b'9' => (c - b'0') as u32,
+        b'A'..=b'N' => (c - b'A') as u32 + 10,
+        _ => (c - b'A') as u32 + 11,
+    }
+}
+
+fn is_curp_consonant(c: u8) -> bool {
+    c.is_ascii_uppercase() && !matches!(c, b'A' | b'E' | b'I' | b'O' | b'U')
+}
+
+// CURP: four name letters, YYMMDD birth date, sex, state, three internal
+// consonants, a century differentiator and a check digit
+fn is_valid_curp(curp: &String) -> bool {
+    if curp.len() != 18 {
+        return false;
+    }
+    let mut c = [0u8; 18];
+    curp.copy_into_slice(&mut c);
+
+    // Name letters; the second is the first internal vowel (X when there is none)
+    if !c[0].is_ascii_uppercase()
+        || !matches!(c[1], b'A' | b'E' | b'I' | b'O' | b'U' | b'X')
+        || !c[2].is_ascii_uppercase()
+        || !c[3].is_ascii_uppercase()
+    {
+        return false;
+    }
+
+    if !c[4..10].iter().all(|d| d.is_ascii_digit()) {
+        return false;
+    }
+
+    // Sex marker: H (hombre), M (mujer) or X (non-binary)
+    if !matches!(c[10], b'H' | b'M' | b'X') {
+        return false;
+    }
+
+    if !CURP_STATES.iter().any(|state| state[..] == c[11..13]) {
+        return false;
+    }
+
+    if !c[13..16].iter().all(|&l| is_curp_consonant(l)) {
+        return false;
+    }
+
+    // The differentiator is a digit for births up to 1999 and a letter from 2000
+    let century = if c[16].is_ascii_digit() {
+        1900
+    } else if c[16].is_ascii_uppercase() {
+        2000
+    } else {
+        return false;
+    };
+
+    let year = century + ((c[4] - b'0') as u32) * 10 + (c[5] - b'0') as u32;
+    let month = ((c[6] - b'0') as u32) * 10 + (c[7] - b'0') as u32;
+    let day = ((c[8] - b'0') as u32) * 10 + (c[9] - b'0') as u32;
+    let leap = (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    if day == 0 || day > days_in_month {
+        return false;
+    }
+
+    if !c[17].is_ascii_digit() {
+        return false;
+    }
+    let sum: u32 = c[..17]
+        .iter()
+        .enumerate()
+        .map(|(i, &ch)| curp_char_value(ch) * (18 - i as u32))
+        .sum();
+    (10 - sum % 10) % 10 == (c[17] - b'0') as u32
+}
+
+// ========== ENHANCED MAIN CONTRACT ==========
+
+#[contract]
+pub struct SaviaContract;
+
+#[contractimpl]
+impl SaviaContract {
+    
+    /// Fix the admin at deployment so initialization cannot be front-run
+    pub fn __constructor(env: Env, admin: Address) {
+        env.storage().instance().set(&DataKey::Admin, &admin);
+    }
+
+    /// Initialize the contract with enhanced Mexican compliance (admin function)
+    pub fn initialize(
+        env: Env,
+        donation_token: Address,
+        platform_fee: u64,
+        etherfuse_config: String,
+        initial_peso_rate: u64,
+    ) -> Result<(), SaviaError> {
+        if env.storage().instance().has(&DataKey::DonationToken) {
+            return Err(SaviaError::AlreadyInitialized);
+        }
+        let admin: Address = env.storage().instance().get(&DataKey::Admin)
+            .ok_or(SaviaError::NotInitialized)?;
+        admin.require_auth();
+
+        if platform_fee > 1000 {
+            return Err(SaviaError::InvalidFee);
+        }
+        
+        env.storage().instance().set(&DataKey::DonationToken, &donation_token);
+        env.storage().instance().set(&DataKey::PlatformFee, &platform_fee);
+        env.storage().instance().set(&DataKey::CampaignCounter, &0u64);
+        env.storage().instance().set(&DataKey::DonationCounter, &0u64);
+        env.storage().instance().set(&DataKey::NFTCounter, &0u64);
+        env.storage().instance().set(&DataKey::MedicalDocCounter, &0u64);
+        env.storage().instance().set(&DataKey::EtherFuseConfig, &etherfuse_config);
+        env.storage().instance().set(&DataKey::PesoExchangeRate, &initial_peso_rate);
+        env.storage().instance().set(&DataKey::PesoRateUpdatedAt, &env.ledger().timestamp());
+        env.storage().instance().set(&DataKey::OracleConfig, &OracleConfig {
+            min_reports: 1,
+            max_deviation_bps: 1000,            // 10%
+            staleness_window: 24 * 60 * 60,     // 1 day
+        });
+        
+        // Initialize verifier lists
+        let empty_vec: Vec<Address> = Vec::new(&env);
+        env.storage().instance().set(&DataKey::KYCVerifiers, &empty_vec);
+        env.storage().instance().set(&DataKey::MedicalVerifiers, &empty_vec);
+        
+        Ok(())
+    }
+
+    /// Register KYC commitments
+    ///
+    /// Personal data is stored only as commitments, so the CURP and phone
+    /// rules run when a verifier opens them, not here. The medical license and
+    /// institution stay in plaintext: cédulas profesionales are published in
+    /// the SEP professional registry and an institution is not personal data.
+    pub fn register_kyc(
+        env: Env,
+        entity: Address,
+        curp_hash: BytesN<32>,
+        full_name_hash: BytesN<32>,
+        phone_hash: BytesN<32>,
+        email_hash: BytesN<32>,
+        address_hash: BytesN<32>,
+        medical_license: Option<String>,
+        institution: Option<String>,
+    ) -> Result<(), SaviaError> {
+        entity.require_auth();
+        Self::require_not_blocked(&env, &entity)?;
+
+        // Submissions, including updates to a verified record, wait for a
+        // KYC verifier to attest them
+        let kyc_record = KYCRecord {
+            entity: entity.clone(),
+            curp_hash,
+            full_name_hash,
+            phone_hash,
+            email_hash,
+            address_hash,
+            proven_fields: Vec::new(&env),
+            revealed_salts: Map::new(&env),
+            verification_level: KYCLevel::Unverified,
+            submitted_at: env.ledger().timestamp(),
+            verified_by: None,
+            verified_at: 0,
+            expires_at: 0,
+            medical_license,
+            institution,
+        };
+
+        env.storage().persistent().set(&DataKey::KYCRecord(entity.clone()), &kyc_record);
+
+        env.events().publish(
+            (symbol_short!("kyc"), symbol_short!("register"), entity),
+            KYCRegisteredEvent { verification_level: KYCLevel::Unverified, expires_at: 0 },
+        );
+        Ok(())
+    }
+
+    /// Attest a personal-data field checked off-chain against its commitment (KYC verifier function)
+    ///
+    /// The holder opens the field to the verifier privately; the verifier
+    /// applies the same CURP and phone rules as `prove_kyc_field` and signs
+    /// the commitment it checked, so no personal data reaches the ledger.
+    pub fn attest_kyc_field(
+        env: Env,
+        verifier: Address,
+        entity: Address,
+        field: KYCField,
+        commitment: BytesN<32>,
+    ) -> Result<(), SaviaError> {
+        Self::require_kyc_verifier(&env, &verifier)?;
+
+        let mut kyc_record: KYCRecord = env.storage().persistent().get(&DataKey::KYCRecord(entity.clone()))
+            .ok_or(SaviaError::KYCNotFound)?;
+
+        // The attestation only counts for the commitment currently on file
+        if commitment != Self::kyc_field_hash(&kyc_record, &field) {
+            return Err(SaviaError::KYCProofMismatch);
+        }
+        if !kyc_record.proven_fields.contains(&field) {
+            kyc_record.proven_fields.push_back(field.clone());
+        }
+        env.storage().persistent().set(&DataKey::KYCRecord(entity.clone()), &kyc_record);
+
+        env.events().publish(
+            (symbol_short!("kyc"), symbol_short!("attested"), entity),
+            KYCFieldProvenEvent { verifier, field },
+        );
+        Ok(())
+    }
+
+    /// Check a personal-data field revealed on-chain against the holder's commitment (KYC verifier function)
+    ///
+    /// CURPs must be well formed and phone numbers are normalized to their
+    /// 10-digit national form before hashing. Proven fields are recorded on
+    /// the KYC record. The value and salt become public in the transaction,
+    /// so personal data should go through `attest_kyc_field` instead, and a
+    /// revealed salt may never open a second field.
+    pub fn prove_kyc_field(
+        env: Env,
+        verifier: Address,
+        entity: Address,
+        field: KYCField,
+        value: String,
+        salt: BytesN<32>,
+    ) -> Result<(), SaviaError> {
+        Self::require_kyc_verifier(&env, &verifier)?;
+
+        let mut kyc_record: KYCRecord = env.storage().persistent().get(&DataKey::KYCRecord(entity.clone()))
+            .ok_or(SaviaError::KYCNotFound)?;
+
+        let value = match field {
+            KYCField::CURP if !is_valid_curp(&value) => return Err(SaviaError::InvalidCURP),
+            KYCField::Phone => normalize_mx_phone(&env, &value).ok_or(SaviaError::InvalidPhoneNumber)?,
+            _ => value,
+        };
+        let commitment = Self::kyc_field_hash(&kyc_record, &field);
+
+        match Self::kyc_commitment(&env, &field, &value, &salt) {
+            Some(hash) if hash == commitment => {}
+            _ => return Err(SaviaError::KYCProofMismatch),
+        }
+
+        match kyc_record.revealed_salts.get(salt.clone()) {
+            Some(opened) if opened != field => return Err(SaviaError::KYCSaltReused),
+            _ => kyc_record.revealed_salts.set(salt, field.clone()),
+        }
+        if !kyc_record.proven_fields.contains(&field) {
+            kyc_record.proven_fields.push_back(field.clone());
+        }
+        env.storage().persistent().set(&DataKey::KYCRecord(entity.clone()), &kyc_record);
+
+        env.events().publish(
+            (symbol_short!("kyc"), symbol_short!("proven"), entity),
+            KYCFieldProvenEvent { verifier, field },
+        );
+        Ok(())
+    }
+
+    /// The commitment stored for a field of a KYC record
+    fn kyc_field_hash(kyc_record: &KYCRecord, field: &KYCField) -> BytesN<32> {
+        match field {
+            KYCField::CURP => kyc_record.curp_hash.clone(),
+            KYCField::FullName => kyc_record.full_name_hash.clone(),
+            KYCField::Phone => kyc_record.phone_hash.clone(),
+            KYCField::Email => kyc_record.email_hash.clone(),
+            KYCField::Address => kyc_record.address_hash.clone(),
+        }
+    }
+
+    /// sha256(field || salt || value), or None when the value is too long to reveal
+    ///
+    /// The field tag keeps a commitment from opening as any other field.
+    fn kyc_commitment(env: &Env, field: &KYCField, value: &String, salt: &BytesN<32>) -> Option<BytesN<32>> {
+        let len = value.len() as usize;
+        if len > MAX_KYC_FIELD_LEN {
+            return None;
+        }
+        let mut buf = [0u8; MAX_KYC_FIELD_LEN];
+        value.copy_into_slice(&mut buf[..len]);
+
+        let mut preimage = Bytes::from_slice(env, &(field.clone() as u32).to_be_bytes());
+        preimage.append(&Bytes::from_slice(env, salt.to_array().as_slice()));
+        preimage.append(&Bytes::from_slice(env, &buf[..len]));
+        Some(env.crypto().sha256(&preimage).into())
+    }
+
+    /// Attest a KYC submission at the given level (KYC verifier function)
+    pub fn verify_kyc(env: Env, verifier: Address, entity: Address, level: KYCLevel) -> Result<(), SaviaError> {
+        Self::require_kyc_verifier(&env, &verifier)?;
+
+        let mut kyc_record: KYCRecord = env.storage().persistent().get(&DataKey::KYCRecord(entity.clone()))
+            .ok_or(SaviaError::KYCNotFound)?;
+
+        // Medical verification needs the license and institution on file
+        let has_medical_credentials = kyc_record.medical_license.is_some() && kyc_record.institution.is_some();
+        match level {
+            KYCLevel::Unverified => return Err(SaviaError::InvalidKYCLevel),
+            KYCLevel::MedicalVerified if !has_medical_credentials => return Err(SaviaError::InvalidKYCLevel),
+            _ => {}
+        }
+
+        // Every attested level rests on a CURP and phone number checked by a
+        // verifier, whether attested off-chain or revealed on-chain
+        if !kyc_record.proven_fields.contains(&KYCField::CURP) || !kyc_record.proven_fields.contains(&KYCField::Phone) {
+            return Err(SaviaError::KYCFieldsNotProven);
+        }
+
+        let current_time = env.ledger().timestamp();
+        kyc_record.verification_level = level.clone();
+        kyc_record.verified_by = Some(verifier.clone());
+        kyc_record.verified_at = current_time;
+        kyc_record.expires_at = current_time + (365 * 24 * 60 * 60); // 1 year validity
+        env.storage().persistent().set(&DataKey::KYCRecord(entity.clone()), &kyc_record);
+        Self::record_kyc_history(&env, &entity, KYCAction::Verified, KYCReason::InitialReview, &level, &verifier);
+        Self::unlock_beneficiary_campaigns(&env, &entity, &verifier);
+
+        env.events().publish(
+            (symbol_short!("kyc"), symbol_short!("verified"), entity),
+            KYCVerifiedEvent { verifier, verification_level: level, expires_at: kyc_record.expires_at },
+        );
+        Ok(())
+    }
+
+    /// Extend a verified KYC record for another year (KYC verifier function)
+    pub fn renew_kyc(env: Env, verifier: Address, entity: Address, reason: KYCReason) -> Result<(), SaviaError> {
+        Self::require_kyc_verifier(&env, &verifier)?;
+
+        let mut kyc_record: KYCRecord = env.storage().persistent().get(&DataKey::KYCRecord(entity.clone()))
+            .ok_or(SaviaError::KYCNotFound)?;
+
+        // Revoked or never-attested records need a fresh verification
+        if kyc_record.verification_level == KYCLevel::Unverified {
+            return Err(SaviaError::KYCNotVerified);
+        }
+
+        let current_time = env.ledger().timestamp();
+        kyc_record.verified_by = Some(verifier.clone());
+        kyc_record.verified_at = current_time;
+        kyc_record.expires_at = current_time + (365 * 24 * 60 * 60);
+        env.storage().persistent().set(&DataKey::KYCRecord(entity.clone()), &kyc_record);
+        Self::record_kyc_history(&env, &entity, KYCAction::Renewed, reason, &kyc_record.verification_level, &verifier);
+        Self::unlock_beneficiary_campaigns(&env, &entity, &verifier);
+
+        env.events().publish(
+            (symbol_short!("kyc"), symbol_short!("renewed"), entity),
+            KYCVerifiedEvent {
+                verifier,
+                verification_level: kyc_record.verification_level,
+                expires_at: kyc_record.expires_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// Revoke a KYC record and lock the holder's campaigns (KYC verifier function)
+    pub fn revoke_kyc(env: Env, verifier: Address, entity: Address, reason: KYCReason) -> Result<u32, SaviaError> {
+        Self::require_kyc_verifier(&env, &verifier)?;
+
+        let mut kyc_record: KYCRecord = env.storage().persistent().get(&DataKey::KYCRecord(entity.clone()))
+            .ok_or(SaviaError::KYCNotFound)?;
+
+        kyc_record.verification_level = KYCLevel::Unverified;
+        kyc_record.expires_at = env.ledger().timestamp();
+        env.storage().persistent().set(&DataKey::KYCRecord(entity.clone()), &kyc_record);
+        Self::record_kyc_history(&env, &entity, KYCAction::Revoked, reason.clone(), &KYCLevel::Unverified, &verifier);
+
+        let campaigns_locked = Self::lock_beneficiary_campaigns(&env, &entity);
+
+        env.events().publish(
+            (symbol_short!("kyc"), symbol_short!("revoked"), entity),
+            KYCRevokedEvent { verifier, reason, campaigns_locked },
+        );
+        Ok(campaigns_locked)
+    }
+
+    /// Lock the campaigns of a beneficiary whose KYC has lapsed (callable by any keeper)
+    pub fn check_kyc_status(env: Env, beneficiary: Address) -> u32 {
+        if Self::kyc_is_current(&env, &beneficiary) {
+            return 0;
+        }
+        Self::lock_beneficiary_campaigns(&env, &beneficiary)
+    }
+
+    /// Get the verification, renewal and revocation history of a KYC record
+    pub fn get_kyc_history(env: Env, entity: Address) -> Vec<KYCHistoryEntry> {
+        env.storage().persistent().get(&DataKey::KYCHistory(entity)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Get the campaigns created by a beneficiary
+    pub fn get_beneficiary_campaigns(env: Env, beneficiary: Address) -> Vec<BytesN<32>> {
+        env.storage().persistent().get(&DataKey::BeneficiaryCampaigns(beneficiary)).unwrap_or(Vec::new(&env))
+    }
+
+    fn require_kyc_verifier(env: &Env, verifier: &Address) -> Result<(), SaviaError> {
+        verifier.require_auth();
+        let verifiers: Vec<Address> = env.storage().instance().get(&DataKey::KYCVerifiers).unwrap_or(Vec::new(env));
+        if !verifiers.contains(verifier) {
+            return Err(SaviaError::NotAuthorized);
+        }
+        Ok(())
+    }
+
+    fn record_kyc_history(env: &Env, entity: &Address, action: KYCAction, reason: KYCReason, level: &KYCLevel, verifier: &Address) {
+        let key = DataKey::KYCHistory(entity.clone());
+        let mut history: Vec<KYCHistoryEntry> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
+        history.push_back(KYCHistoryEntry {
+            action,
+            reason,
+            level: level.clone(),
+            verifier: verifier.clone(),
+            timestamp: env.ledger().timestamp(),
+        });
+        env.storage().persistent().set(&key, &history);
+    }
+
+    /// Whether an address holds an attested, unexpired KYC record
+    fn kyc_is_current(env: &Env, entity: &Address) -> bool {
+        match env.storage().persistent().get::<DataKey, KYCRecord>(&DataKey::KYCRecord(entity.clone())) {
+            Some(record) => record.verification_level != KYCLevel::Unverified && record.expires_at >= env.ledger().timestamp(),
+            None => false,
+        }
+    }
+
+    /// Lock every open campaign of a beneficiary, returning how many were locked
+    fn lock_beneficiary_campaigns(env: &Env, beneficiary: &Address) -> u32 {
+        let current_time = env.ledger().timestamp();
+        let campaign_ids: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::BeneficiaryCampaigns(beneficiary.clone())).unwrap_or(Vec::new(env));
+        let mut locked = 0;
+        for campaign_id in campaign_ids.iter() {
+            if let Some(mut campaign) = env.storage().persistent().get::<DataKey, Campaign>(&DataKey::Campaign(campaign_id.clone())) {
+                if campaign.funds_locked || campaign.refunding {
+                    continue;
+                }
+                campaign.funds_locked = true;
+                campaign.lock_reason = LockReason::KYCLapsed;
+                env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+                locked += 1;
+
+                env.events().publish(
+                    (symbol_short!("campaign"), symbol_short!("locked"), campaign_id),
+                    CampaignLockedEvent { proof_deadline: campaign.proof_deadline, locked_at: current_time },
+                );
+            }
+        }
+        locked
+    }
+
+    /// Lift the KYC locks on a beneficiary's campaigns once their KYC is current again
+    ///
+    /// Locks for a missed proof deadline or an emergency pause stay in place.
+    fn unlock_beneficiary_campaigns(env: &Env, beneficiary: &Address, verifier: &Address) {
+        let campaign_ids: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::BeneficiaryCampaigns(beneficiary.clone())).unwrap_or(Vec::new(env));
+        for campaign_id in campaign_ids.iter() {
+            if let Some(mut campaign) = env.storage().persistent().get::<DataKey, Campaign>(&DataKey::Campaign(campaign_id.clone())) {
+                if campaign.lock_reason != LockReason::KYCLapsed || campaign.refunding {
+                    continue;
+                }
+                campaign.funds_locked = false;
+                campaign.lock_reason = LockReason::Unlocked;
+                env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+
+                env.events().publish((symbol_short!("campaign"), symbol_short!("resumed"), campaign_id), verifier.clone());
+            }
+        }
+    }
+
+    /// Create a new campaign with enhanced medical requirements
+    pub fn create_campaign(
+        env: Env,
+        beneficiary: Address,
+        title: String,
+        description: String,
+        medical_condition: String,
+        goal_amount: u64,
+        duration_days: u64,
+        category: String,
+        location: String,
+        etherfuse_account: String,
+    ) -> Result<BytesN<32>, SaviaError> {
+        beneficiary.require_auth();
+        Self::require_not_blocked(&env, &beneficiary)?;
+
+        // Validate KYC verification
+        let kyc_record: KYCRecord = env.storage().persistent().get(&DataKey::KYCRecord(beneficiary.clone()))
+            .ok_or(SaviaError::KYCNotVerified)?;
+
+        if kyc_record.verification_level == KYCLevel::Unverified {
+            return Err(SaviaError::KYCNotVerified);
+        }
+
+        if kyc_record.expires_at < env.ledger().timestamp() {
+            return Err(SaviaError::KYCExpired);
+        }
+
+        // Validate inputs
+        if goal_amount == 0 {
+            return Err(SaviaError::InvalidGoal);
+        }
+        
+        if duration_days == 0 || duration_days > 365 {
+            return Err(SaviaError::InvalidDuration);
+        }
+
+        // Get current peso exchange rate
+        let peso_rate = Self::current_peso_rate(&env).rate;
+
+        // The beneficiary's KYC tier caps how much a campaign may raise
+        let peso_goal = Self::scale(goal_amount, peso_rate)?;
+        let limits = Self::get_tier_limits(env.clone(), kyc_record.verification_level.clone());
+        if limits.max_campaign_goal > 0 && peso_goal > Self::limit_in_pesos(&env, limits.max_campaign_goal) {
+            return Err(SaviaError::CampaignGoalLimitExceeded);
+        }
+
+        // Get and increment campaign counter
+        let counter: u64 = env.storage().instance().get(&DataKey::CampaignCounter).unwrap_or(0);
+        let new_counter = counter + 1;
+        env.storage().instance().set(&DataKey::CampaignCounter, &new_counter);
+
+        // Generate campaign ID
+        let current_time = env.ledger().timestamp();
+        let mut hash_input = Bytes::new(&env);
+        
+        let beneficiary_bytes = beneficiary.clone().to_xdr(&env);
+        let title_bytes = title.to_val().to_xdr(&env);
+               
+        hash_input.append(&beneficiary_bytes);
+        hash_input.append(&title_bytes);
+        hash_input.append(&Bytes::from_slice(&env, &goal_amount.to_be_bytes()));
+        hash_input.append(&Bytes::from_slice(&env, &current_time.to_be_bytes()));
+        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
+        
+        let campaign_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+
+        let end_time = current_time + (duration_days * 24 * 60 * 60);
+        let proof_deadline = current_time + (30 * 24 * 60 * 60); // 30 days for first proof
+
+        let campaign = Campaign {
+            id: campaign_id.clone(),
+            title,
+            description,
+            beneficiary,
+            goal_amount,
+            current_amount: 0,
+            start_time: current_time,
+            end_time,
+            verified: false,
+            trust_score: 0,
+            category,
+            location,
+            medical_condition,
+            kyc_verified: true,
+            medical_docs_verified: false,
+            last_proof_submitted: 0,
+            proof_deadline,
+            funds_locked: false,
+            lock_reason: LockReason::Unlocked,
+            etherfuse_account,
+            peso_exchange_rate: peso_rate,
+            peso_raised: 0,
+            released_amount: 0,
+            payout_amount: 0,
+            refunding: false,
+            refund_started_at: 0,
+        };
+
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+        env.storage().persistent().set(&DataKey::CampaignRegistry(new_counter), &campaign_id);
+
+        let beneficiary_key = DataKey::BeneficiaryCampaigns(campaign.beneficiary.clone());
+        let mut beneficiary_campaigns: Vec<BytesN<32>> = env.storage().persistent().get(&beneficiary_key).unwrap_or(Vec::new(&env));
+        beneficiary_campaigns.push_back(campaign_id.clone());
+        env.storage().persistent().set(&beneficiary_key, &beneficiary_campaigns);
+
+        env.events().publish(
+            (symbol_short!("campaign"), symbol_short!("created"), campaign_id.clone()),
+            CampaignCreatedEvent {
+                beneficiary: campaign.beneficiary,
+                goal_amount,
+                end_time,
+                category: campaign.category,
+            },
+        );
+        Ok(campaign_id)
+    }
+
+    /// Submit medical documentation
+    pub fn submit_medical_documentation(
+        env: Env,
+        campaign_id: BytesN<32>,
+        document_type: MedicalDocType,
+        document_url: String,
+        notes: String,
+    ) -> Result<BytesN<32>, SaviaError> {
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        // Only the beneficiary can submit documentation for their campaign
+        campaign.beneficiary.require_auth();
+
+        // Get and increment medical doc counter
+        let counter: u64 = env.storage().instance().get(&DataKey::MedicalDocCounter).unwrap_or(0);
+        let new_counter = counter + 1;
+        env.storage().instance().set(&DataKey::MedicalDocCounter, &new_counter);
+
+        // Generate document hash from URL and content
+        let mut hash_input = Bytes::new(&env);
+        hash_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
+        hash_input.append(&document_url.to_val().to_xdr(&env));
+        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
+        
+        let document_hash: BytesN<32> = env.crypto().sha256(&hash_input).into();
+
+        let current_time = env.ledger().timestamp();
+        let expiry_date = current_time + (90 * 24 * 60 * 60); // 90 days validity
+
+        let medical_doc = MedicalDocumentation {
+            campaign_id: campaign_id.clone(),
+            document_type: document_type.clone(),
+            document_hash: document_hash.clone(),
+            document_url,
+            submitted_at: current_time,
+            verified_by: None,
+            verification_status: DocumentStatus::Pending,
+            expiry_date,
+            notes,
+        };
+
+        // Update campaign
+        campaign.last_proof_submitted = current_time;
+        campaign.proof_deadline = current_time + (30 * 24 * 60 * 60); // Reset 30-day deadline
+        // New proof only lifts a lock caused by missing proof; pauses and
+        // KYC locks stay until the admin resumes the campaign
+        if !campaign.refunding
+            && campaign.lock_reason == LockReason::ProofDeadline
+            && Self::kyc_is_current(&env, &campaign.beneficiary)
+        {
+            campaign.funds_locked = false;
+            campaign.lock_reason = LockReason::Unlocked;
+        }
+
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+        env.storage().persistent().set(&DataKey::MedicalDoc(document_hash.clone()), &medical_doc);
+
+        // Link the document to its campaign so milestones can find it
+        let mut campaign_docs: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::CampaignDocs(campaign_id.clone())).unwrap_or(Vec::new(&env));
+        campaign_docs.push_back(document_hash.clone());
+        env.storage().persistent().set(&DataKey::CampaignDocs(campaign_id.clone()), &campaign_docs);
+
+        env.events().publish(
+            (symbol_short!("medical"), symbol_short!("submitted"), campaign_id),
+            DocumentSubmittedEvent { document_hash: document_hash.clone(), document_type },
+        );
+        Ok(document_hash)
+    }
+
+    /// Verify medical documentation (admin function)
+    pub fn verify_medical_documentation(
+        env: Env,
+        document_hash: BytesN<32>,
+        verifier: Address,
+        approved: bool,
+    ) -> Result<(), SaviaError> {
+        verifier.require_auth();
+
+        // Check if verifier is authorized
+        let verifiers: Vec<Address> = env.storage().instance().get(&DataKey::MedicalVerifiers).unwrap_or(Vec::new(&env));
+        if !verifiers.contains(&verifier) {
+            return Err(SaviaError::NotAuthorized);
+        }
+
+        let mut medical_doc: MedicalDocumentation = env.storage().persistent().get(&DataKey::MedicalDoc(document_hash.clone()))
+            .ok_or(SaviaError::DocumentNotFound)?;
+
+        medical_doc.verified_by = Some(verifier.clone());
+        medical_doc.verification_status = if approved { DocumentStatus::Verified } else { DocumentStatus::Rejected };
+
+        // Update campaign verification status
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(medical_doc.campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        if approved {
+            campaign.medical_docs_verified = true;
+            campaign.verified = true;
+        }
+
+        env.storage().persistent().set(&DataKey::MedicalDoc(document_hash.clone()), &medical_doc);
+        env.storage().persistent().set(&DataKey::Campaign(medical_doc.campaign_id.clone()), &campaign);
+
+        env.events().publish(
+            (symbol_short!("medical"), symbol_short!("verified"), medical_doc.campaign_id),
+            DocumentVerifiedEvent { document_hash, verifier, approved },
+        );
+        Ok(())
+    }
+
+    /// Add a disbursement milestone backed by the documents it requires
+    ///
+    /// The plan is fixed once the first donation arrives, so donors give
+    /// against the release gating they were shown.
+    pub fn add_milestone(
+        env: Env,
+        campaign_id: BytesN<32>,
+        amount: u64,
+        required_docs: Vec<MedicalDocType>,
+    ) -> Result<u32, SaviaError> {
+        let campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        campaign.beneficiary.require_auth();
+
+        if amount == 0 {
+            return Err(SaviaError::InvalidAmount);
+        }
+
+        if required_docs.is_empty() {
+            return Err(SaviaError::InvalidMedicalDoc);
+        }
+
+        if Self::get_campaign_donation_count(env.clone(), campaign_id.clone()) > 0 {
+            return Err(SaviaError::MilestonePlanLocked);
+        }
+
+        // Milestones can never promise more than the campaign goal
+        let mut milestones: Vec<Milestone> = env.storage().persistent().get(&DataKey::Milestones(campaign_id.clone())).unwrap_or(Vec::new(&env));
+        let planned: u64 = milestones.iter().map(|m| m.amount).sum();
+        match planned.checked_add(amount) {
+            Some(total) if total <= campaign.goal_amount => {}
+            _ => return Err(SaviaError::InvalidAmount),
+        }
+
+        milestones.push_back(Milestone {
+            amount,
+            required_docs,
+            linked_docs: Vec::new(&env),
+            released: false,
+            released_at: 0,
+        });
+        env.storage().persistent().set(&DataKey::Milestones(campaign_id), &milestones);
+
+        Ok(milestones.len() - 1)
+    }
+
+    /// Release a milestone to the beneficiary once its documents are verified
+    pub fn release_milestone(env: Env, campaign_id: BytesN<32>, milestone_index: u32) -> Result<u64, SaviaError> {
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        campaign.beneficiary.require_auth();
+        Self::require_not_blocked(&env, &campaign.beneficiary)?;
+
+        if campaign.funds_locked {
+            return Err(SaviaError::FundsLocked);
+        }
+
+        // Funds only reach a beneficiary whose KYC is still current
+        if !Self::kyc_is_current(&env, &campaign.beneficiary) {
+            return Err(SaviaError::KYCExpired);
+        }
+
+        let mut milestones: Vec<Milestone> = env.storage().persistent().get(&DataKey::Milestones(campaign_id.clone())).unwrap_or(Vec::new(&env));
+        let mut milestone = milestones.get(milestone_index)
+            .ok_or(SaviaError::MilestoneNotFound)?;
+
+        if milestone.released {
+            return Err(SaviaError::MilestoneAlreadyReleased);
+        }
+
+        // Each required document type must be backed by a verified, unexpired
+        // document that has not already been used to release another milestone
+        let current_time = env.ledger().timestamp();
+        let mut used_docs: Vec<BytesN<32>> = Vec::new(&env);
+        for released in milestones.iter() {
+            used_docs.append(&released.linked_docs);
+        }
+        let campaign_docs: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::CampaignDocs(campaign_id.clone())).unwrap_or(Vec::new(&env));
+        let mut linked_docs: Vec<BytesN<32>> = Vec::new(&env);
+        for doc_type in milestone.required_docs.iter() {
+            let doc_hash = campaign_docs.iter().find(|hash| {
+                if used_docs.contains(hash) || linked_docs.contains(hash) {
+                    return false;
+                }
+                match env.storage().persistent().get::<DataKey, MedicalDocumentation>(&DataKey::MedicalDoc(hash.clone())) {
+                    Some(doc) => {
+                        doc.document_type == doc_type
+                            && doc.verification_status == DocumentStatus::Verified
+                            && doc.expiry_date >= current_time
+                    }
+                    None => false,
+                }
+            }).ok_or(SaviaError::MilestoneDocsNotVerified)?;
+            linked_docs.push_back(doc_hash);
+        }
+
+        let escrow: u64 = env.storage().persistent().get(&DataKey::CampaignEscrow(campaign_id.clone())).unwrap_or(0);
+        if escrow < milestone.amount {
+            return Err(SaviaError::InsufficientFunds);
+        }
+
+        let donation_token: Address = env.storage().instance().get(&DataKey::DonationToken)
+            .ok_or(SaviaError::NotInitialized)?;
+        token::Client::new(&env, &donation_token).transfer(&env.current_contract_address(), &campaign.beneficiary, &(milestone.amount as i128));
+
+        env.storage().persistent().set(&DataKey::CampaignEscrow(campaign_id.clone()), &(escrow - milestone.amount));
+
+        milestone.linked_docs = linked_docs;
+        milestone.released = true;
+        milestone.released_at = current_time;
+        milestones.set(milestone_index, milestone.clone());
+        env.storage().persistent().set(&DataKey::Milestones(campaign_id.clone()), &milestones);
+
+        campaign.released_amount += milestone.amount;
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+
+        env.events().publish(
+            (symbol_short!("milestone"), symbol_short!("released"), campaign_id),
+            MilestoneReleasedEvent {
+                milestone_index,
+                beneficiary: campaign.beneficiary,
+                amount: milestone.amount,
+            },
+        );
+        Ok(milestone.amount)
+    }
+
+    /// Request an MXN payout of released funds to a CLABE account (beneficiary function)
+    ///
+    /// `release_milestone` has already paid the tokens to the beneficiary, so
+    /// this pulls them back into custody until an EtherFuse operator (the same
+    /// set that settles donation conversions) settles the payout or fails it
+    /// and sends them back.
+    pub fn request_payout(env: Env, campaign_id: BytesN<32>, amount: u64, clabe: String) -> Result<u64, SaviaError> {
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        campaign.beneficiary.require_auth();
+        Self::require_not_blocked(&env, &campaign.beneficiary)?;
+
+        if !is_valid_clabe(&clabe) {
+            return Err(SaviaError::InvalidCLABE);
+        }
+        if amount == 0 {
+            return Err(SaviaError::InvalidAmount);
+        }
+        if amount > campaign.released_amount - campaign.payout_amount {
+            return Err(SaviaError::InsufficientFunds);
+        }
+        let exchange_rate = Self::current_peso_rate(&env).rate;
+        let peso_amount = Self::scale(amount, exchange_rate)?;
+
+        let donation_token: Address = env.storage().instance().get(&DataKey::DonationToken)
+            .ok_or(SaviaError::NotInitialized)?;
+        token::Client::new(&env, &donation_token).transfer(&campaign.beneficiary, &env.current_contract_address(), &(amount as i128));
+
+        campaign.payout_amount += amount;
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+
+        let payout_id: u64 = env.storage().instance().get(&DataKey::PayoutCounter).unwrap_or(0) + 1;
+        env.storage().instance().set(&DataKey::PayoutCounter, &payout_id);
+
+        let payout = Payout {
+            id: payout_id,
+            campaign_id: campaign_id.clone(),
+            beneficiary: campaign.beneficiary,
+            clabe,
+            amount,
+            peso_amount,
+            exchange_rate,
+            status: PayoutStatus::Requested,
+            external_id: String::from_str(&env, ""),
+            requested_at: env.ledger().timestamp(),
+            settled_at: 0,
+        };
+        env.storage().persistent().set(&DataKey::Payout(payout_id), &payout);
+
+        let mut payouts: Vec<u64> = env.storage().persistent().get(&DataKey::CampaignPayouts(campaign_id.clone())).unwrap_or(Vec::new(&env));
+        payouts.push_back(payout_id);
+        env.storage().persistent().set(&DataKey::CampaignPayouts(campaign_id.clone()), &payouts);
+
+        env.events().publish(
+            (symbol_short!("payout"), symbol_short!("requested"), campaign_id),
+            PayoutEvent { payout_id, amount, status: PayoutStatus::Requested },
+        );
+        Ok(payout_id)
+    }
+
+    /// Load a requested payout and move it to `next`, checking the operator
+    fn close_payout(
+        env: &Env,
+        operator: &Address,
+        payout_id: u64,
+        external_id: String,
+        next: PayoutStatus,
+    ) -> Result<Payout, SaviaError> {
+        operator.require_auth();
+
+        let operators: Vec<Address> = env.storage().instance().get(&DataKey::EtherFuseOperators).unwrap_or(Vec::new(env));
+        if !operators.contains(operator) {
+            return Err(SaviaError::NotAuthorized);
+        }
+
+        let mut payout: Payout = env.storage().persistent().get(&DataKey::Payout(payout_id))
+            .ok_or(SaviaError::PayoutNotFound)?;
+        if payout.status != PayoutStatus::Requested {
+            return Err(SaviaError::InvalidPayoutStatus);
+        }
+
+        payout.status = next;
+        payout.external_id = external_id;
+        payout.settled_at = env.ledger().timestamp();
+        env.storage().persistent().set(&DataKey::Payout(payout_id), &payout);
+        Ok(payout)
+    }
+
+    /// Settle a payout once the MXN transfer has been sent (anchor operator function)
+    ///
+    /// The held tokens go to the operator, who funded the peso transfer.
+    pub fn settle_payout(env: Env, operator: Address, payout_id: u64, external_id: String) -> Result<(), SaviaError> {
+        let payout = Self::close_payout(&env, &operator, payout_id, external_id, PayoutStatus::Settled)?;
+
+        let donation_token: Address = env.storage().instance().get(&DataKey::DonationToken)
+            .ok_or(SaviaError::NotInitialized)?;
+        token::Client::new(&env, &donation_token).transfer(&env.current_contract_address(), &operator, &(payout.amount as i128));
+
+        env.events().publish(
+            (symbol_short!("payout"), symbol_short!("settled"), payout.campaign_id),
+            PayoutEvent { payout_id, amount: payout.amount, status: PayoutStatus::Settled },
+        );
+        Ok(())
+    }
+
+    /// Fail a payout and return the held tokens to the beneficiary (anchor operator function)
+    pub fn fail_payout(env: Env, operator: Address, payout_id: u64, external_id: String) -> Result<(), SaviaError> {
+        let payout = Self::close_payout(&env, &operator, payout_id, external_id, PayoutStatus::Failed)?;
+
+        let donation_token: Address = env.storage().instance().get(&DataKey::DonationToken)
+            .ok_or(SaviaError::NotInitialized)?;
+        token::Client::new(&env, &donation_token).transfer(&env.current_contract_address(), &payout.beneficiary, &(payout.amount as i128));
+
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(payout.campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+        campaign.payout_amount = campaign.payout_amount.saturating_sub(payout.amount);
+        env.storage().persistent().set(&DataKey::Campaign(payout.campaign_id.clone()), &campaign);
+
+        env.events().publish(
+            (symbol_short!("payout"), symbol_short!("failed"), payout.campaign_id),
+            PayoutEvent { payout_id, amount: payout.amount, status: PayoutStatus::Failed },
+        );
+        Ok(())
+    }
+
+    /// Get a payout
+    pub fn get_payout(env: Env, payout_id: u64) -> Option<Payout> {
+        env.storage().persistent().get(&DataKey::Payout(payout_id))
+    }
+
+    /// List a campaign's payouts, oldest first
+    pub fn list_campaign_payouts(env: Env, campaign_id: BytesN<32>, start: u32, limit: u32) -> Vec<Payout> {
+        let index: Vec<u64> = env.storage().persistent().get(&DataKey::CampaignPayouts(campaign_id)).unwrap_or(Vec::new(&env));
+        let mut page = Vec::new(&env);
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(index.len());
+        for i in start..end {
+            if let Some(payout) = env.storage().persistent().get::<DataKey, Payout>(&DataKey::Payout(index.get_unchecked(i))) {
+                page.push_back(payout);
+            }
+        }
+        page
+    }
+
+    /// Process donation with peso conversion and dynamic NFT
+    pub fn donate(
+        env: Env,
+        campaign_id: BytesN<32>,
+        donor: Address,
+        xlm_amount: u64,
+        anonymous: bool,
+        mint_nft: bool,
+        min_peso_amount: Option<u64>,
+        max_rate_age: Option<u64>,
+    ) -> Result<BytesN<32>, SaviaError> {
+        donor.require_auth();
+        Self::require_not_blocked(&env, &donor)?;
+
+        // Validate campaign exists and is active
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+        Self::require_not_blocked(&env, &campaign.beneficiary)?;
+
+        let current_time = env.ledger().timestamp();
+        
+        // Check if campaign has ended
+        if current_time > campaign.end_time {
+            return Err(SaviaError::CampaignEnded);
+        }
+
+        // Check if funds are locked due to missing documentation
+        if campaign.funds_locked {
+            return Err(SaviaError::FundsLocked);
+        }
+
+        // Beneficiaries must keep their KYC current to receive donations
+        if !Self::kyc_is_current(&env, &campaign.beneficiary) {
+            return Err(SaviaError::KYCExpired);
+        }
+
+        // Check if medical documentation is expired
+        if campaign.last_proof_submitted > 0 && (current_time > campaign.proof_deadline) {
+            campaign.funds_locked = true;
+            campaign.lock_reason = LockReason::ProofDeadline;
+            env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+            return Err(SaviaError::ProofDeadlineExceeded);
+        }
+
+        if xlm_amount == 0 {
+            return Err(SaviaError::InvalidAmount);
+        }
+
+        // Refuse to convert with a rate nobody has confirmed recently
+        let oracle_config = Self::get_oracle_config(env.clone());
+        let rate_quote = Self::current_peso_rate(&env);
+        if current_time > rate_quote.updated_at + oracle_config.staleness_window {
+            return Err(SaviaError::StaleExchangeRate);
+        }
+
+        // The donor may demand a fresher rate than the platform-wide window
+        if let Some(max_age) = max_rate_age {
+            if current_time > rate_quote.updated_at + max_age {
+                return Err(SaviaError::StaleExchangeRate);
+            }
+        }
+
+        // Get platform fee
+        let platform_fee_rate: u64 = env.storage().instance().get(&DataKey::PlatformFee).unwrap_or(200);
+        let platform_fee = Self::scale(xlm_amount, platform_fee_rate)?;
+        let net_xlm_amount = xlm_amount - platform_fee;
+        let net_peso_amount = Self::scale(net_xlm_amount, rate_quote.rate)?;
+
+        // Protect the donor against the rate moving after they signed
+        if let Some(min_peso) = min_peso_amount {
+            if net_peso_amount < min_peso {
+                return Err(SaviaError::SlippageExceeded);
+            }
+        }
+
+        // Legal limits apply to what the donor gives, fee included
+        let gross_peso_amount = Self::scale(xlm_amount, rate_quote.rate)?;
+        Self::check_donation_limits(&env, &donor, gross_peso_amount)?;
+
+        // Pull the donated asset from the donor into contract custody
+        let donation_token: Address = env.storage().instance().get(&DataKey::DonationToken)
+            .ok_or(SaviaError::NotInitialized)?;
+        token::Client::new(&env, &donation_token).transfer(&donor, &env.current_contract_address(), &(xlm_amount as i128));
+
+        // Escrow the net amount for the campaign and keep the fee apart
+        let escrow: u64 = env.storage().persistent().get(&DataKey::CampaignEscrow(campaign_id.clone())).unwrap_or(0);
+        env.storage().persistent().set(&DataKey::CampaignEscrow(campaign_id.clone()), &(escrow + net_xlm_amount));
+        let mut fee_account = Self::get_fee_account(env.clone(), donation_token.clone());
+        fee_account.collected += platform_fee;
+        env.storage().persistent().set(&DataKey::FeeAccount(donation_token), &fee_account);
+
+        // Generate donation ID
+        let counter: u64 = env.storage().instance().get(&DataKey::DonationCounter).unwrap_or(0);
+        let new_counter = counter + 1;
+        env.storage().instance().set(&DataKey::DonationCounter, &new_counter);
+
+        let mut hash_input = Bytes::new(&env);
+        hash_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
+        hash_input.append(&donor.clone().to_xdr(&env));
+        hash_input.append(&Bytes::from_slice(&env, &xlm_amount.to_be_bytes()));
+        hash_input.append(&Bytes::from_slice(&env, &current_time.to_be_bytes()));
+        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
+        
+        let donation_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+
+        // Create EtherFuse transaction
+        let etherfuse_tx_id = Self::create_etherfuse_transaction(
+            env.clone(),
+            donation_id.clone(),
+            campaign_id.clone(),
+            donor.clone(),
+            net_peso_amount,
+            net_xlm_amount,
+            rate_quote.rate,
+            campaign.etherfuse_account.clone(),
+        )?;
+
+        // Create donation record
+        let donation = Donation {
+            id: donation_id.clone(),
+            campaign_id: campaign_id.clone(),
+            donor: donor.clone(),
+            amount: net_xlm_amount,
+            peso_amount: net_peso_amount,
+            gross_peso_amount,
+            exchange_rate: rate_quote.rate,
+            timestamp: current_time,
+            nft_minted: mint_nft,
+            anonymous,
+            refunded: false,
+            etherfuse_tx_id: Some(etherfuse_tx_id),
+        };
+
+        // Update campaign progress
+        campaign.current_amount += net_xlm_amount;
+        campaign.peso_raised += net_peso_amount;
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+
+        // Store donation
+        env.storage().persistent().set(&DataKey::Donation(donation_id.clone()), &donation);
+        Self::index_donation(&env, &donation);
+        Self::track_aml_activity(&env, &donation, &campaign.beneficiary);
+
+        // Update trust score
+        Self::update_donor_trust_score(env.clone(), donor.clone(), net_peso_amount)?;
+
+        env.events().publish(
+            (symbol_short!("donation"), symbol_short!("received"), campaign_id.clone()),
+            DonationEvent {
+                donation_id: donation_id.clone(),
+                donor: if anonymous { None } else { Some(donor.clone()) },
+                amount: net_xlm_amount,
+                peso_amount: net_peso_amount,
+                platform_fee,
+            },
+        );
+
+        // Handle dynamic NFT
+        if mint_nft {
+            Self::mint_or_update_dynamic_nft(env.clone(), donor, campaign_id, net_xlm_amount)?;
+        }
+
+        Ok(donation_id)
+    }
+
+    /// Create EtherFuse transaction for peso conversion
+    fn create_etherfuse_transaction(
+        env: Env,
+        donation_id: BytesN<32>,
+        campaign_id: BytesN<32>,
+        donor: Address,
+        peso_amount: u64,
+        xlm_amount: u64,
+        exchange_rate: u64,
+        _etherfuse_account: String,
+    ) -> Result<BytesN<32>, SaviaError> {
+        let counter: u64 = env.storage().instance().get(&DataKey::EtherFuseCounter).unwrap_or(0);
+        let new_counter = counter + 1;
+        env.storage().instance().set(&DataKey::EtherFuseCounter, &new_counter);
+
+        // Generate transaction hash; the counter keeps same-ledger conversions apart
+        let mut hash_input = Bytes::new(&env);
+        hash_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
+        hash_input.append(&donor.clone().to_xdr(&env));
+        hash_input.append(&Bytes::from_slice(&env, &peso_amount.to_be_bytes()));
+        hash_input.append(&Bytes::from_slice(&env, &env.ledger().timestamp().to_be_bytes()));
+        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
+
+        let tx_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+
+        let etherfuse_tx = EtherFuseTransaction {
+            id: tx_id.clone(),
+            donation_id: donation_id.clone(),
+            campaign_id: campaign_id.clone(),
+            donor,
+            peso_amount,
+            xlm_amount,
+            exchange_rate,
+            etherfuse_tx_id: String::from_str(&env, ""),
+            status: TransactionStatus::Pending,
+            timestamp: env.ledger().timestamp(),
+            settled_at: 0,
+        };
+
+        env.storage().persistent().set(&DataKey::EtherFuseTransaction(tx_id.clone()), &etherfuse_tx);
+        env.storage().persistent().set(&DataKey::DonationConversion(donation_id), &tx_id);
+
+        let conversions_key = DataKey::CampaignConversions(campaign_id);
+        let mut conversions: Vec<BytesN<32>> = env.storage().persistent().get(&conversions_key).unwrap_or(Vec::new(&env));
+        conversions.push_back(tx_id.clone());
+        env.storage().persistent().set(&conversions_key, &conversions);
+
+        Ok(tx_id)
+    }
+
+    /// Mint or update dynamic NFT tree
+    fn mint_or_update_dynamic_nft(
+        env: Env,
+        donor: Address,
+        campaign_id: BytesN<32>,
+        peso_amount: u64,
+    ) -> Result<(), SaviaError> {
+        let nft_key = Self::donor_nft_key(&env, &donor, &campaign_id);
+
+        // Check if NFT already exists
+        if let Some(mut nft) = env.storage().persistent().get::<DataKey, DynamicNFT>(&DataKey::DynamicNFT(nft_key.clone())) {
+            // Update existing NFT
+            nft.total_donated += peso_amount;
+            nft.donation_count += 1;
+            nft.last_updated = env.ledger().timestamp();
+            
+            // Update growth stage based on total donated
+            let previous_stage = nft.growth_stage.clone();
+            nft.growth_stage = Self::calculate_growth_stage(nft.total_donated);
+            if nft.growth_stage != previous_stage {
+                env.events().publish(
+                    (symbol_short!("nft"), symbol_short!("grown"), donor.clone()),
+                    NFTGrowthEvent {
+                        nft_id: nft_key.clone(),
+                        previous_stage,
+                        growth_stage: nft.growth_stage.clone(),
+                    },
+                );
+            }
+            
+            // Check for achievements
+            if nft.donation_count == 10 {
+                nft.special_achievements.push_back(String::from_str(&env, "Consistent Supporter"));
+            }
+            if nft.total_donated >= 1_000_000_000 { // 100+ XLM
+                nft.special_achievements.push_back(String::from_str(&env, "Major Donor"));
+            }
+
+            env.storage().persistent().set(&DataKey::DynamicNFT(nft_key), &nft);
+        } else {
+            // Create new NFT
+            let current_time = env.ledger().timestamp();
+            let growth_stage = Self::calculate_growth_stage(peso_amount);
+            
+            let nft = DynamicNFT {
+                id: nft_key.clone(),
+                owner: donor.clone(),
+                campaign_id: campaign_id.clone(),
+                tree_level: 1,
+                total_donated: peso_amount,
+                donation_count: 1,
+                created_at: current_time,
+                last_updated: current_time,
+                metadata_uri: String::from_str(&env, "https://drive.google.com/file/d/1RadoLAjnG00YPC3F2PNnB49PfuMorNZ8/view"),
+                growth_stage: growth_stage.clone(),
+                special_achievements: Vec::new(&env),
+            };
+
+            env.storage().persistent().set(&DataKey::DynamicNFT(nft_key.clone()), &nft);
+
+            env.events().publish(
+                (symbol_short!("nft"), symbol_short!("minted"), donor),
+                NFTMintedEvent { nft_id: nft_key, campaign_id, growth_stage },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Calculate tree growth stage based on total XLM donated (in stroops)
+    fn calculate_growth_stage(total_stroops: u64) -> TreeGrowthStage {
+        match total_stroops {
+            0..=99_999_999 => TreeGrowthStage::PreSeed,       // < 10 XLM
+            100_000_000..=249_999_999 => TreeGrowthStage::Seed,     // 10-24 XLM
+            250_000_000..=499_999_999 => TreeGrowthStage::Sprout,   // 25-49 XLM
+            500_000_000..=999_999_999 => TreeGrowthStage::Bloom,    // 50-99 XLM
+            1_000_000_000..=1_999_999_999 => TreeGrowthStage::Blossom, // 100-199 XLM
+            2_000_000_000..=4_999_999_999 => TreeGrowthStage::Tree,    // 200-499 XLM
+            _ => TreeGrowthStage::SaviaTree,                           // 500+ XLM
+        }
+    }
+
+    /// Check and process expired proof deadlines
+    pub fn check_proof_deadlines(env: Env, campaign_id: BytesN<32>) -> Result<(), SaviaError> {
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        let current_time = env.ledger().timestamp();
+
+        // Check if 30 days have passed without proof submission
+        if !campaign.refunding && campaign.last_proof_submitted > 0 && current_time > campaign.proof_deadline {
+            // Reduce trust score for late submission
+            if let Some(mut trust_score) = env.storage().persistent().get::<DataKey, TrustScore>(&DataKey::TrustScore(campaign.beneficiary.clone())) {
+                trust_score.late_submissions += 1;
+                trust_score.score = trust_score.score.saturating_sub(20);
+                env.storage().persistent().set(&DataKey::TrustScore(campaign.beneficiary.clone()), &trust_score);
+            }
+
+            Self::initiate_refund_process(&env, &mut campaign);
+
+            env.events().publish(
+                (symbol_short!("campaign"), symbol_short!("locked"), campaign_id.clone()),
+                CampaignLockedEvent { proof_deadline: campaign.proof_deadline, locked_at: current_time },
+            );
+        }
+
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id), &campaign);
+        Ok(())
+    }
+
+    /// Start refunds for a campaign (admin function)
+    pub fn start_campaign_refunds(env: Env, campaign_id: BytesN<32>) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        if !campaign.refunding {
+            Self::initiate_refund_process(&env, &mut campaign);
+            env.storage().persistent().set(&DataKey::Campaign(campaign_id), &campaign);
+        }
+        Ok(())
+    }
+
+    /// Initiate refund process for donors
+    fn initiate_refund_process(env: &Env, campaign: &mut Campaign) {
+        // Lock the campaign for good and snapshot what is left in escrow, so
+        // every donor gets the same share back whatever order they claim in
+        campaign.funds_locked = true;
+        campaign.refunding = true;
+        campaign.refund_started_at = env.ledger().timestamp();
+
+        let escrow: u64 = env.storage().persistent().get(&DataKey::CampaignEscrow(campaign.id.clone())).unwrap_or(0);
+        let pool = RefundPool {
+            escrow_at_start: escrow,
+            raised_at_start: campaign.current_amount,
+        };
+        env.storage().persistent().set(&DataKey::RefundPool(campaign.id.clone()), &pool);
+
+        env.events().publish((symbol_short!("refund"), symbol_short!("started"), campaign.id.clone()), pool);
+    }
+
+    /// Get the contract admin
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Admin)
+    }
+
+    /// Require authorization from the stored admin
+    fn require_admin(env: &Env) -> Result<Address, SaviaError> {
+        if !env.storage().instance().has(&DataKey::DonationToken) {
+            return Err(SaviaError::NotInitialized);
+        }
+        let admin: Address = env.storage().instance().get(&DataKey::Admin)
+            .ok_or(SaviaError::NotInitialized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    /// Update peso exchange rate (admin function)
+    pub fn update_peso_exchange_rate(env: Env, new_rate: u64) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        if new_rate == 0 {
+            return Err(SaviaError::InvalidExchangeRate);
+        }
+        Self::set_peso_rate(&env, new_rate, 0);
+        Ok(())
+    }
+
+    /// Store a new peso exchange rate and announce it
+    fn set_peso_rate(env: &Env, rate: u64, report_count: u32) {
+        let updated_at = env.ledger().timestamp();
+        env.storage().instance().set(&DataKey::PesoExchangeRate, &rate);
+        env.storage().instance().set(&DataKey::PesoRateUpdatedAt, &updated_at);
+
+        env.events().publish(
+            (symbol_short!("rate"), symbol_short!("updated")),
+            RateUpdatedEvent { rate, report_count, updated_at },
+        );
+    }
+
+    /// Configure rate aggregation and staleness (admin function)
+    pub fn set_oracle_config(env: Env, config: OracleConfig) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        if config.min_reports == 0 || config.max_deviation_bps == 0 || config.staleness_window == 0 {
+            return Err(SaviaError::InvalidExchangeRate);
+        }
+        env.storage().instance().set(&DataKey::OracleConfig, &config);
+        Ok(())
+    }
+
+    /// Add an authorized exchange-rate reporter (admin function)
+    pub fn add_rate_reporter(env: Env, reporter: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut reporters: Vec<Address> = env.storage().instance().get(&DataKey::RateReporters).unwrap_or(Vec::new(&env));
+        if !reporters.contains(&reporter) {
+            reporters.push_back(reporter);
+            env.storage().instance().set(&DataKey::RateReporters, &reporters);
+        }
+        Ok(())
+    }
+
+    /// Remove an exchange-rate reporter and its pending report (admin function)
+    pub fn remove_rate_reporter(env: Env, reporter: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut reporters: Vec<Address> = env.storage().instance().get(&DataKey::RateReporters).unwrap_or(Vec::new(&env));
+        if let Some(index) = reporters.first_index_of(&reporter) {
+            reporters.remove(index);
+            env.storage().instance().set(&DataKey::RateReporters, &reporters);
+        }
+        let mut reports: Map<Address, RateReport> = env.storage().instance().get(&DataKey::RateReports).unwrap_or(Map::new(&env));
+        reports.remove(reporter);
+        env.storage().instance().set(&DataKey::RateReports, &reports);
+        Ok(())
+    }
+
+    /// Submit an XLM/MXN rate; the stored rate becomes the median of fresh reports
+    pub fn submit_peso_rate(env: Env, reporter: Address, rate: u64) -> Result<u64, SaviaError> {
+        reporter.require_auth();
+
+        let reporters: Vec<Address> = env.storage().instance().get(&DataKey::RateReporters).unwrap_or(Vec::new(&env));
+        if !reporters.contains(&reporter) {
+            return Err(SaviaError::NotAuthorized);
+        }
+
+        if rate == 0 {
+            return Err(SaviaError::InvalidExchangeRate);
+        }
+
+        // Reject reports that jump too far from the last accepted rate
+        let config = Self::get_oracle_config(env.clone());
+        let last_rate: u64 = env.storage().instance().get(&DataKey::PesoExchangeRate).unwrap_or(180000);
+        let deviation = rate.abs_diff(last_rate) as u128 * 10000 / last_rate as u128;
+        if deviation > config.max_deviation_bps as u128 {
+            return Err(SaviaError::RateDeviationTooLarge);
+        }
+
+        let current_time = env.ledger().timestamp();
+        let mut reports: Map<Address, RateReport> = env.storage().instance().get(&DataKey::RateReports).unwrap_or(Map::new(&env));
+        reports.set(reporter, RateReport { rate, timestamp: current_time });
+        env.storage().instance().set(&DataKey::RateReports, &reports);
+
+        // Aggregate the reports that are still fresh
+        let mut fresh_rates: Vec<u64> = Vec::new(&env);
+        for (_, report) in reports.iter() {
+            if current_time <= report.timestamp + config.staleness_window {
+                fresh_rates.push_back(report.rate);
+            }
+        }
+
+        if fresh_rates.len() < config.min_reports {
+            return Ok(last_rate);
+        }
+
+        let median = Self::median_rate(&fresh_rates);
+        Self::set_peso_rate(&env, median, fresh_rates.len());
+        Ok(median)
+    }
+
+    /// Median of a non-empty list of rates
+    fn median_rate(rates: &Vec<u64>) -> u64 {
+        // Insertion sort; the list holds one entry per reporter
+        let mut sorted = rates.clone();
+        for i in 1..sorted.len() {
+            let mut j = i;
+            while j > 0 && sorted.get_unchecked(j - 1) > sorted.get_unchecked(j) {
+                let previous = sorted.get_unchecked(j - 1);
+                sorted.set(j - 1, sorted.get_unchecked(j));
+                sorted.set(j, previous);
+                j -= 1;
+            }
+        }
+
+        let middle = sorted.len() / 2;
+        if sorted.len().is_multiple_of(2) {
+            (sorted.get_unchecked(middle - 1) + sorted.get_unchecked(middle)) / 2
+        } else {
+            sorted.get_unchecked(middle)
+        }
+    }
+
+    /// Get the rate aggregation and staleness settings
+    pub fn get_oracle_config(env: Env) -> OracleConfig {
+        env.storage().instance().get(&DataKey::OracleConfig).unwrap_or(OracleConfig {
+            min_reports: 1,
+            max_deviation_bps: 1000,
+            staleness_window: 24 * 60 * 60,
+        })
+    }
+
+    /// Get the authorized exchange-rate reporters
+    pub fn get_rate_reporters(env: Env) -> Vec<Address> {
+        env.storage().instance().get(&DataKey::RateReporters).unwrap_or(Vec::new(&env))
+    }
+
+    /// Get the latest report from each reporter
+    pub fn get_rate_reports(env: Env) -> Map<Address, RateReport> {
+        env.storage().instance().get(&DataKey::RateReports).unwrap_or(Map::new(&env))
+    }
+
+    /// Get when the peso exchange rate was last updated
+    pub fn get_peso_rate_updated_at(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::PesoRateUpdatedAt).unwrap_or(0)
+    }
+
+    /// Update platform fee in basis points (admin function)
+    pub fn set_platform_fee(env: Env, platform_fee: u64) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        if platform_fee > 1000 {
+            return Err(SaviaError::InvalidFee);
+        }
+        env.storage().instance().set(&DataKey::PlatformFee, &platform_fee);
+        Ok(())
+    }
+
+    /// Set the treasury address allowed to withdraw fees (admin function)
+    pub fn set_treasury(env: Env, treasury: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().set(&DataKey::Treasury, &treasury);
+        Ok(())
+    }
+
+    /// Withdraw accrued platform fees (admin or treasury)
+    pub fn withdraw_fees(env: Env, caller: Address, asset: Address, to: Address, amount: u64) -> Result<(), SaviaError> {
+        caller.require_auth();
+
+        let admin: Option<Address> = env.storage().instance().get(&DataKey::Admin);
+        let treasury: Option<Address> = env.storage().instance().get(&DataKey::Treasury);
+        if admin.as_ref() != Some(&caller) && treasury.as_ref() != Some(&caller) {
+            return Err(SaviaError::NotAuthorized);
+        }
+
+        if amount == 0 {
+            return Err(SaviaError::InvalidAmount);
+        }
+
+        let mut fee_account = Self::get_fee_account(env.clone(), asset.clone());
+        if fee_account.collected - fee_account.withdrawn < amount {
+            return Err(SaviaError::InsufficientFunds);
+        }
+
+        fee_account.withdrawn += amount;
+        env.storage().persistent().set(&DataKey::FeeAccount(asset.clone()), &fee_account);
+        token::Client::new(&env, &asset).transfer(&env.current_contract_address(), &to, &(amount as i128));
+
+        env.events().publish(
+            (symbol_short!("fees"), symbol_short!("withdrawn"), caller),
+            FeesWithdrawnEvent { asset, to, amount },
+        );
+        Ok(())
+    }
+
+    /// Add KYC verifier (admin function)
+    pub fn add_kyc_verifier(env: Env, verifier: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut verifiers: Vec<Address> = env.storage().instance().get(&DataKey::KYCVerifiers).unwrap_or(Vec::new(&env));
+        if !verifiers.contains(&verifier) {
+            verifiers.push_back(verifier);
+            env.storage().instance().set(&DataKey::KYCVerifiers, &verifiers);
+        }
+        Ok(())
+    }
+
+    /// Add medical verifier (admin function)
+    pub fn add_medical_verifier(env: Env, verifier: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut verifiers: Vec<Address> = env.storage().instance().get(&DataKey::MedicalVerifiers).unwrap_or(Vec::new(&env));
+        if !verifiers.contains(&verifier) {
+            verifiers.push_back(verifier);
+            env.storage().instance().set(&DataKey::MedicalVerifiers, &verifiers);
+        }
+        Ok(())
+    }
+
+    /// Add a compliance officer (admin function)
+    pub fn add_compliance_officer(env: Env, officer: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut officers: Vec<Address> = env.storage().instance().get(&ComplianceKey::ComplianceOfficers).unwrap_or(Vec::new(&env));
+        if !officers.contains(&officer) {
+            officers.push_back(officer);
+            env.storage().instance().set(&ComplianceKey::ComplianceOfficers, &officers);
+        }
+        Ok(())
+    }
+
+    /// Remove a compliance officer (admin function)
+    pub fn remove_compliance_officer(env: Env, officer: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut officers: Vec<Address> = env.storage().instance().get(&ComplianceKey::ComplianceOfficers).unwrap_or(Vec::new(&env));
+        if let Some(index) = officers.first_index_of(&officer) {
+            officers.remove(index);
+            env.storage().instance().set(&ComplianceKey::ComplianceOfficers, &officers);
+        }
+        Ok(())
+    }
+
+    /// Get the compliance officers
+    pub fn get_compliance_officers(env: Env) -> Vec<Address> {
+        env.storage().instance().get(&ComplianceKey::ComplianceOfficers).unwrap_or(Vec::new(&env))
+    }
+
+    /// Require a signature from the admin or a compliance officer
+    fn require_compliance(env: &Env, caller: &Address) -> Result<(), SaviaError> {
+        caller.require_auth();
+        let admin: Address = env.storage().instance().get(&DataKey::Admin)
+            .ok_or(SaviaError::NotInitialized)?;
+        let officers: Vec<Address> = env.storage().instance().get(&ComplianceKey::ComplianceOfficers).unwrap_or(Vec::new(env));
+        if *caller != admin && !officers.contains(caller) {
+            return Err(SaviaError::NotAuthorized);
+        }
+        Ok(())
+    }
+
+    /// Set the limit unit, UMA value, verified-donor threshold and period (admin or compliance)
+    pub fn set_compliance_config(env: Env, caller: Address, config: ComplianceConfig) -> Result<(), SaviaError> {
+        Self::require_compliance(&env, &caller)?;
+        if config.period == 0 || (config.unit == LimitUnit::Uma && config.uma_value == 0) {
+            return Err(SaviaError::InvalidAmount);
+        }
+        env.storage().instance().set(&ComplianceKey::ComplianceConfig, &config);
+        Ok(())
+    }
+
+    /// Set the caps for one KYC tier (admin or compliance)
+    pub fn set_tier_limits(env: Env, caller: Address, level: KYCLevel, limits: TierLimits) -> Result<(), SaviaError> {
+        Self::require_compliance(&env, &caller)?;
+        env.storage().instance().set(&ComplianceKey::TierLimits(level), &limits);
+        Ok(())
+    }
+
+    /// Get the compliance configuration
+    pub fn get_compliance_config(env: Env) -> ComplianceConfig {
+        env.storage().instance().get(&ComplianceKey::ComplianceConfig).unwrap_or(ComplianceConfig {
+            unit: LimitUnit::Pesos,
+            uma_value: 0,
+            verified_donor_threshold: 0,
+            period: 30 * 24 * 60 * 60,
+        })
+    }
+
+    /// Get the caps for one KYC tier; unconfigured tiers are uncapped
+    pub fn get_tier_limits(env: Env, level: KYCLevel) -> TierLimits {
+        env.storage().instance().get(&ComplianceKey::TierLimits(level)).unwrap_or(TierLimits {
+            max_donation: 0,
+            max_period_total: 0,
+            max_campaign_goal: 0,
+        })
+    }
+
+    /// Get what a donor has given in the current limit period
+    pub fn get_donor_period_total(env: Env, donor: Address) -> DonorPeriodTotal {
+        let config = Self::get_compliance_config(env.clone());
+        match env.storage().persistent().get::<ComplianceKey, DonorPeriodTotal>(&ComplianceKey::DonorPeriodTotal(donor)) {
+            Some(period) if env.ledger().timestamp() < period.period_start + config.period => period,
+            _ => DonorPeriodTotal { period_start: env.ledger().timestamp(), total: 0 },
+        }
+    }
+
+    /// Convert a threshold in the configured unit to pesos
+    fn limit_in_pesos(env: &Env, amount: u64) -> u64 {
+        let config = Self::get_compliance_config(env.clone());
+        match config.unit {
+            LimitUnit::Pesos => amount,
+            LimitUnit::Uma => amount.saturating_mul(config.uma_value),
+        }
+    }
+
+    /// Apply the donor's KYC tier caps to a donation and count it towards the period total
+    fn check_donation_limits(env: &Env, donor: &Address, peso_amount: u64) -> Result<(), SaviaError> {
+        let config = Self::get_compliance_config(env.clone());
+
+        // Donors without current KYC are treated as unverified
+        let level = match env.storage().persistent().get::<DataKey, KYCRecord>(&DataKey::KYCRecord(donor.clone())) {
+            Some(record) if Self::kyc_is_current(env, donor) => record.verification_level,
+            _ => KYCLevel::Unverified,
+        };
+
+        if config.verified_donor_threshold > 0
+            && level == KYCLevel::Unverified
+            && peso_amount > Self::limit_in_pesos(env, config.verified_donor_threshold)
+        {
+            return Err(SaviaError::KYCNotVerified);
+        }
+
+        let limits = Self::get_tier_limits(env.clone(), level);
+        if limits.max_donation > 0 && peso_amount > Self::limit_in_pesos(env, limits.max_donation) {
+            return Err(SaviaError::DonationLimitExceeded);
+        }
+
+        let mut period = Self::get_donor_period_total(env.clone(), donor.clone());
+        period.total = period.total.saturating_add(peso_amount);
+        if limits.max_period_total > 0 && period.total > Self::limit_in_pesos(env, limits.max_period_total) {
+            return Err(SaviaError::DonationLimitExceeded);
+        }
+        env.storage().persistent().set(&ComplianceKey::DonorPeriodTotal(donor.clone()), &period);
+        Ok(())
+    }
+
+    /// Block an address from donating, creating campaigns, registering KYC or reporting fraud (admin or compliance)
+    pub fn block_address(env: Env, caller: Address, address: Address) -> Result<(), SaviaError> {
+        Self::require_compliance(&env, &caller)?;
+        env.storage().persistent().set(&ComplianceKey::Blocked(address.clone()), &true);
+        env.events().publish((symbol_short!("blocklist"), symbol_short!("added"), address), caller);
+        Ok(())
+    }
+
+    /// Remove an address from the blocklist (admin or compliance)
+    pub fn unblock_address(env: Env, caller: Address, address: Address) -> Result<(), SaviaError> {
+        Self::require_compliance(&env, &caller)?;
+        env.storage().persistent().remove(&ComplianceKey::Blocked(address.clone()));
+        env.events().publish((symbol_short!("blocklist"), symbol_short!("removed"), address), caller);
+        Ok(())
+    }
+
+    /// Whether an address is on the blocklist
+    pub fn is_blocked(env: Env, address: Address) -> bool {
+        env.storage().persistent().get(&ComplianceKey::Blocked(address)).unwrap_or(false)
+    }
+
+    /// Screen addresses through an external contract as well (admin or compliance)
+    pub fn set_screening(env: Env, caller: Address, config: ScreeningConfig) -> Result<(), SaviaError> {
+        Self::require_compliance(&env, &caller)?;
+        env.storage().instance().set(&ComplianceKey::Screening, &config);
+        Ok(())
+    }
+
+    /// Stop consulting the external screening contract (admin or compliance)
+    pub fn clear_screening(env: Env, caller: Address) -> Result<(), SaviaError> {
+        Self::require_compliance(&env, &caller)?;
+        env.storage().instance().remove(&ComplianceKey::Screening);
+        Ok(())
+    }
+
+    /// Get the external screening configuration
+    pub fn get_screening(env: Env) -> Option<ScreeningConfig> {
+        env.storage().instance().get(&ComplianceKey::Screening)
+    }
+
+    /// Refuse blocked addresses and, when configured, addresses the screening
+    /// contract rejects; a failing screening call counts as a rejection
+    fn require_not_blocked(env: &Env, address: &Address) -> Result<(), SaviaError> {
+        if Self::is_blocked(env.clone(), address.clone()) {
+            return Err(SaviaError::AddressBlocked);
+        }
+
+        if let Some(config) = env.storage().instance().get::<ComplianceKey, ScreeningConfig>(&ComplianceKey::Screening) {
+            let listed = match ScreeningClient::new(env, &config.contract).try_is_listed(address) {
+                Ok(Ok(listed)) => listed,
+                _ => return Err(SaviaError::AddressBlocked),
+            };
+            let allowed = match config.mode {
+                ScreeningMode::Allowlist => listed,
+                ScreeningMode::Denylist => !listed,
+            };
+            if !allowed {
+                return Err(SaviaError::AddressBlocked);
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the AML aviso threshold and rolling window (admin or compliance)
+    pub fn set_aml_config(env: Env, caller: Address, config: AmlConfig) -> Result<(), SaviaError> {
+        Self::require_compliance(&env, &caller)?;
+        if config.window == 0 {
+            return Err(SaviaError::InvalidAmount);
+        }
+        env.storage().instance().set(&ComplianceKey::AmlConfig, &config);
+        Ok(())
+    }
+
+    /// Get the AML aviso configuration
+    pub fn get_aml_config(env: Env) -> AmlConfig {
+        env.storage().instance().get(&ComplianceKey::AmlConfig).unwrap_or(AmlConfig {
+            threshold: 0,
+            window: 180 * 24 * 60 * 60, // Six months
+        })
+    }
+
+    /// List AML alerts, oldest first, for compliance review (admin or compliance)
+    pub fn list_aml_alerts(env: Env, caller: Address, start: u32, limit: u32) -> Result<Vec<AmlAlert>, SaviaError> {
+        Self::require_compliance(&env, &caller)?;
+        let count: u64 = env.storage().instance().get(&ComplianceKey::AmlAlertCounter).unwrap_or(0);
+        let mut page = Vec::new(&env);
+        let end = (start as u64).saturating_add(limit.min(MAX_PAGE_SIZE) as u64).min(count);
+        for id in (start as u64 + 1)..=end {
+            if let Some(alert) = env.storage().persistent().get::<ComplianceKey, AmlAlert>(&ComplianceKey::AmlAlert(id)) {
+                page.push_back(alert);
+            }
+        }
+        Ok(page)
+    }
+
+    /// Get the number of AML alerts raised
+    pub fn get_aml_alert_count(env: Env) -> u64 {
+        env.storage().instance().get(&ComplianceKey::AmlAlertCounter).unwrap_or(0)
+    }
+
+    /// Add a donation's gross peso amount to the donor's rolling total for the
+    /// beneficiary, across all of their campaigns, and raise an alert when it
+    /// crosses the threshold
+    fn track_aml_activity(env: &Env, donation: &Donation, beneficiary: &Address) {
+        let config = Self::get_aml_config(env.clone());
+        if config.threshold == 0 {
+            return;
+        }
+
+        let current_time = env.ledger().timestamp();
+        let key = ComplianceKey::AmlActivity(donation.donor.clone(), beneficiary.clone());
+        let activity: Vec<AmlEntry> = env.storage().persistent().get(&key).unwrap_or(Vec::new(env));
+
+        // Drop donations that have left the window
+        let mut recent = Vec::new(env);
+        let mut previous_total: u64 = 0;
+        for entry in activity.iter() {
+            if entry.timestamp + config.window > current_time {
+                previous_total = previous_total.saturating_add(entry.peso_amount);
+                recent.push_back(entry);
+            }
+        }
+        recent.push_back(AmlEntry { donation_id: donation.id.clone(), timestamp: current_time, peso_amount: donation.gross_peso_amount });
+        env.storage().persistent().set(&key, &recent);
+
+        let rolling_total = previous_total.saturating_add(donation.gross_peso_amount);
+        let threshold = Self::limit_in_pesos(env, config.threshold);
+        if previous_total >= threshold || rolling_total < threshold {
+            return;
+        }
+
+        let alert_id: u64 = env.storage().instance().get(&ComplianceKey::AmlAlertCounter).unwrap_or(0) + 1;
+        env.storage().instance().set(&ComplianceKey::AmlAlertCounter, &alert_id);
+        let alert = AmlAlert {
+            id: alert_id,
+            donor: donation.donor.clone(),
+            campaign_id: donation.campaign_id.clone(),
+            beneficiary: beneficiary.clone(),
+            donation_id: donation.id.clone(),
+            rolling_total,
+            threshold,
+            timestamp: current_time,
+        };
+        env.storage().persistent().set(&ComplianceKey::AmlAlert(alert_id), &alert);
+
+        env.events().publish(
+            (symbol_short!("aml"), symbol_short!("alert"), donation.campaign_id.clone()),
+            AmlAlertEvent { alert_id, rolling_total, threshold },
+        );
+    }
+
+    /// Get campaign details
+    pub fn get_campaign(env: Env, campaign_id: BytesN<32>) -> Option<Campaign> {
+        env.storage().persistent().get(&DataKey::Campaign(campaign_id))
+    }
+
+    /// List campaigns matching a filter, in creation order
+    ///
+    /// `cursor` is the registry position to resume from (0 starts at the
+    /// beginning). A page may hold fewer than `limit` campaigns when only part
+    /// of the registry was inspected; keep following `next_cursor`.
+    pub fn list_campaigns(env: Env, filter: CampaignFilter, cursor: u64, limit: u32) -> CampaignPage {
+        let total: u64 = env.storage().instance().get(&DataKey::CampaignCounter).unwrap_or(0);
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let current_time = env.ledger().timestamp();
+
+        let mut campaigns = Vec::new(&env);
+        let mut position = cursor.max(1);
+        let scan_end = position.saturating_add(MAX_SCAN_SIZE);
+        while position <= total && position < scan_end && campaigns.len() < limit {
+            if let Some(campaign_id) = env.storage().persistent().get::<DataKey, BytesN<32>>(&DataKey::CampaignRegistry(position)) {
+                if let Some(campaign) = env.storage().persistent().get::<DataKey, Campaign>(&DataKey::Campaign(campaign_id)) {
+                    if Self::campaign_matches(&campaign, &filter, current_time) {
+                        campaigns.push_back(campaign);
+                    }
+                }
+            }
+            position += 1;
+        }
+
+        CampaignPage {
+            campaigns,
+            next_cursor: if position <= total { Some(position) } else { None },
+        }
+    }
+
+    /// Check a campaign against a listing filter
+    fn campaign_matches(campaign: &Campaign, filter: &CampaignFilter, current_time: u64) -> bool {
+        if let Some(category) = &filter.category {
+            if *category != campaign.category {
+                return false;
+            }
+        }
+        if let Some(location) = &filter.location {
+            if *location != campaign.location {
+                return false;
+            }
+        }
+        if let Some(verified) = filter.verified {
+            if verified != campaign.verified {
+                return false;
+            }
+        }
+        if let Some(active) = filter.active {
+            let campaign_active = !campaign.refunding && current_time <= campaign.end_time;
+            if active != campaign_active {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Get the number of campaigns ever created
+    pub fn get_campaign_count(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::CampaignCounter).unwrap_or(0)
+    }
+
+    /// Get donation details
+    pub fn get_donation(env: Env, donation_id: BytesN<32>) -> Option<Donation> {
+        env.storage().persistent().get(&DataKey::Donation(donation_id))
+    }
+
+    /// Get KYC record
+    pub fn get_kyc_record(env: Env, entity: Address) -> Option<KYCRecord> {
+        env.storage().persistent().get(&DataKey::KYCRecord(entity))
+    }
+
+    /// Get medical documentation
+    pub fn get_medical_documentation(env: Env, doc_hash: BytesN<32>) -> Option<MedicalDocumentation> {
+        env.storage().persistent().get(&DataKey::MedicalDoc(doc_hash))
+    }
+
+    /// Get the milestones defined for a campaign
+    pub fn get_milestones(env: Env, campaign_id: BytesN<32>) -> Vec<Milestone> {
+        env.storage().persistent().get(&DataKey::Milestones(campaign_id)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Get dynamic NFT
+    pub fn get_dynamic_nft(env: Env, nft_id: BytesN<32>) -> Option<DynamicNFT> {
+        env.storage().persistent().get(&DataKey::DynamicNFT(nft_id))
+    }
+
+    /// Get donor's NFT for a specific campaign
+    pub fn get_donor_nft(env: Env, donor: Address, campaign_id: BytesN<32>) -> Option<DynamicNFT> {
+        let nft_key = Self::donor_nft_key(&env, &donor, &campaign_id);
+        env.storage().persistent().get(&DataKey::DynamicNFT(nft_key))
+    }
+
+    /// Key of the NFT a donor grows for one campaign
+    fn donor_nft_key(env: &Env, donor: &Address, campaign_id: &BytesN<32>) -> BytesN<32> {
+        let mut nft_key_input = Bytes::new(env);
+        nft_key_input.append(&donor.clone().to_xdr(env));
+        nft_key_input.append(&Bytes::from_slice(env, campaign_id.to_array().as_slice()));
+        env.crypto().sha256(&nft_key_input).into()
+    }
+
+    /// Get the asset accepted for donations
+    pub fn get_donation_token(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::DonationToken)
+    }
+
+    /// Get the amount held in escrow for a campaign
+    pub fn get_campaign_escrow(env: Env, campaign_id: BytesN<32>) -> u64 {
+        env.storage().persistent().get(&DataKey::CampaignEscrow(campaign_id)).unwrap_or(0)
+    }
+
+    /// Get the platform fees collected and withdrawn for an asset
+    pub fn get_fee_account(env: Env, asset: Address) -> FeeAccount {
+        env.storage().persistent().get(&DataKey::FeeAccount(asset))
+            .unwrap_or(FeeAccount { collected: 0, withdrawn: 0 })
+    }
+
+    /// Get the platform fee in basis points
+    pub fn get_platform_fee(env: Env) -> u64 {
+        env.storage().instance().get(&DataKey::PlatformFee).unwrap_or(200)
+    }
+
+    /// Get the treasury address allowed to withdraw fees
+    pub fn get_treasury(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Treasury)
+    }
+
+    /// Get trust score
+    pub fn get_trust_score(env: Env, entity: Address) -> Option<TrustScore> {
+        env.storage().persistent().get(&DataKey::TrustScore(entity))
+    }
+
+    /// Get EtherFuse transaction
+    pub fn get_etherfuse_transaction(env: Env, tx_id: BytesN<32>) -> Option<EtherFuseTransaction> {
+        env.storage().persistent().get(&DataKey::EtherFuseTransaction(tx_id))
+    }
+
+    /// Get the peso conversion made for a donation
+    pub fn get_donation_conversion(env: Env, donation_id: BytesN<32>) -> Option<EtherFuseTransaction> {
+        let tx_id: BytesN<32> = env.storage().persistent().get(&DataKey::DonationConversion(donation_id))?;
+        env.storage().persistent().get(&DataKey::EtherFuseTransaction(tx_id))
+    }
+
+    /// List a campaign's peso conversions, oldest first, for reconciliation
+    pub fn list_campaign_conversions(env: Env, campaign_id: BytesN<32>, start: u32, limit: u32) -> Vec<EtherFuseTransaction> {
+        let index: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::CampaignConversions(campaign_id)).unwrap_or(Vec::new(&env));
+        let mut page = Vec::new(&env);
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(index.len());
+        for i in start..end {
+            if let Some(etherfuse_tx) = env.storage().persistent().get::<DataKey, EtherFuseTransaction>(&DataKey::EtherFuseTransaction(index.get_unchecked(i))) {
+                page.push_back(etherfuse_tx);
+            }
+        }
+        page
+    }
+
+    /// Get the number of peso conversions made for a campaign
+    pub fn get_campaign_conversion_count(env: Env, campaign_id: BytesN<32>) -> u32 {
+        let index: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::CampaignConversions(campaign_id)).unwrap_or(Vec::new(&env));
+        index.len()
+    }
+
+    /// Add an EtherFuse operator allowed to settle conversions (admin function)
+    pub fn add_etherfuse_operator(env: Env, operator: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut operators: Vec<Address> = env.storage().instance().get(&DataKey::EtherFuseOperators).unwrap_or(Vec::new(&env));
+        if !operators.contains(&operator) {
+            operators.push_back(operator);
+            env.storage().instance().set(&DataKey::EtherFuseOperators, &operators);
+        }
+        Ok(())
+    }
+
+    /// Remove an EtherFuse operator (admin function)
+    pub fn remove_etherfuse_operator(env: Env, operator: Address) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        let mut operators: Vec<Address> = env.storage().instance().get(&DataKey::EtherFuseOperators).unwrap_or(Vec::new(&env));
+        if let Some(index) = operators.first_index_of(&operator) {
+            operators.remove(index);
+            env.storage().instance().set(&DataKey::EtherFuseOperators, &operators);
+        }
+        Ok(())
+    }
+
+    /// Get the EtherFuse operators
+    pub fn get_etherfuse_operators(env: Env) -> Vec<Address> {
+        env.storage().instance().get(&DataKey::EtherFuseOperators).unwrap_or(Vec::new(&env))
+    }
+
+    /// Load a conversion and move it to `next`, checking the operator and the transition
+    fn settle_etherfuse_transaction(
+        env: &Env,
+        operator: &Address,
+        tx_id: &BytesN<32>,
+        external_id: String,
+        next: TransactionStatus,
+    ) -> Result<EtherFuseTransaction, SaviaError> {
+        operator.require_auth();
+
+        let operators: Vec<Address> = env.storage().instance().get(&DataKey::EtherFuseOperators).unwrap_or(Vec::new(env));
+        if !operators.contains(operator) {
+            return Err(SaviaError::NotAuthorized);
+        }
+
+        let mut etherfuse_tx: EtherFuseTransaction = env.storage().persistent().get(&DataKey::EtherFuseTransaction(tx_id.clone()))
+            .ok_or(SaviaError::TransactionNotFound)?;
+        if !etherfuse_tx.status.can_transition_to(&next) {
+            return Err(SaviaError::InvalidTransactionStatus);
+        }
+
+        etherfuse_tx.status = next;
+        etherfuse_tx.etherfuse_tx_id = external_id;
+        etherfuse_tx.settled_at = env.ledger().timestamp();
+        env.storage().persistent().set(&DataKey::EtherFuseTransaction(tx_id.clone()), &etherfuse_tx);
+        Ok(etherfuse_tx)
+    }
+
+    /// Confirm a pending peso conversion and attach the EtherFuse reference (operator function)
+    pub fn confirm_etherfuse_transaction(
+        env: Env,
+        operator: Address,
+        tx_id: BytesN<32>,
+        external_id: String,
+    ) -> Result<(), SaviaError> {
+        let etherfuse_tx = Self::settle_etherfuse_transaction(&env, &operator, &tx_id, external_id, TransactionStatus::Confirmed)?;
+
+        env.events().publish(
+            (symbol_short!("etherfuse"), symbol_short!("confirmed"), tx_id),
+            ConversionSettledEvent {
+                donation_id: etherfuse_tx.donation_id,
+                external_id: etherfuse_tx.etherfuse_tx_id,
+                reversed_amount: 0,
+            },
+        );
+        Ok(())
+    }
+
+    /// Fail a pending peso conversion and reverse its donation (operator function)
+    ///
+    /// The donor gets the same pro-rata share of the escrow that a refund
+    /// would pay and the campaign totals drop accordingly; the platform fee
+    /// is kept, as with refunds.
+    pub fn fail_etherfuse_transaction(
+        env: Env,
+        operator: Address,
+        tx_id: BytesN<32>,
+        external_id: String,
+    ) -> Result<u64, SaviaError> {
+        let etherfuse_tx = Self::settle_etherfuse_transaction(&env, &operator, &tx_id, external_id, TransactionStatus::Failed)?;
+
+        let mut donation: Donation = env.storage().persistent().get(&DataKey::Donation(etherfuse_tx.donation_id.clone()))
+            .ok_or(SaviaError::DonationNotFound)?;
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(donation.campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        // Released milestones may have drawn the escrow below the raised
+        // total, so every reversal takes the same share of what is left
+        let escrow: u64 = env.storage().persistent().get(&DataKey::CampaignEscrow(campaign.id.clone())).unwrap_or(0);
+        let reversed_amount = Self::escrow_share(&env, &campaign, &donation)?.min(escrow);
+        if reversed_amount > 0 {
+            let donation_token: Address = env.storage().instance().get(&DataKey::DonationToken)
+                .ok_or(SaviaError::NotInitialized)?;
+            token::Client::new(&env, &donation_token).transfer(&env.current_contract_address(), &donation.donor, &(reversed_amount as i128));
+        }
+        env.storage().persistent().set(&DataKey::CampaignEscrow(campaign.id.clone()), &(escrow - reversed_amount));
+
+        campaign.current_amount = campaign.current_amount.saturating_sub(donation.amount);
+        campaign.peso_raised = campaign.peso_raised.saturating_sub(donation.peso_amount);
+        env.storage().persistent().set(&DataKey::Campaign(campaign.id.clone()), &campaign);
+
+        donation.refunded = true;
+        env.storage().persistent().set(&DataKey::Donation(donation.id.clone()), &donation);
+        Self::reverse_donation_totals(&env, &donation, &campaign.beneficiary);
+
+        env.events().publish(
+            (symbol_short!("etherfuse"), symbol_short!("failed"), tx_id),
+            ConversionSettledEvent {
+                donation_id: donation.id,
+                external_id: etherfuse_tx.etherfuse_tx_id,
+                reversed_amount,
+            },
+        );
+        Ok(reversed_amount)
+    }
+
+    /// Initialize enhanced trust score (admin function)
+    pub fn initialize_trust_score(env: Env, entity: Address) -> Result<(), SaviaError> {
+        // Self-issued scores would get around the reporter trust gate
+        Self::require_admin(&env)?;
+
+        if env.storage().persistent().has(&DataKey::TrustScore(entity.clone())) {
+            return Err(SaviaError::ScoreExists);
+        }
+
+        let trust_score = TrustScore {
+            entity: entity.clone(),
+            score: 50, // Start with neutral score
+            verification_level: 0,
+            donation_count: 0,
+            total_donated: 0,
+            campaigns_created: 0,
+            medical_docs_submitted: 0,
+            docs_verified_on_time: 0,
+            late_submissions: 0,
+            fraud_reports: 0,
+            last_updated: env.ledger().timestamp(),
+        };
+
+        env.storage().persistent().set(&DataKey::TrustScore(entity), &trust_score);
+        Ok(())
+    }
+
+    /// Enhanced trust score update
+    fn update_donor_trust_score(env: Env, donor: Address, peso_amount: u64) -> Result<(), SaviaError> {
+        let mut trust_score: TrustScore = env.storage().persistent().get(&DataKey::TrustScore(donor.clone()))
+            .unwrap_or(TrustScore {
+                entity: donor.clone(),
+                score: 50,
+                verification_level: 0,
+                donation_count: 0,
+                total_donated: 0,
+                campaigns_created: 0,
+                medical_docs_submitted: 0,
+                docs_verified_on_time: 0,
+                late_submissions: 0,
+                fraud_reports: 0,
+                last_updated: env.ledger().timestamp(),
+            });
+
+        trust_score.donation_count += 1;
+        trust_score.total_donated += peso_amount;
+        trust_score.last_updated = env.ledger().timestamp();
+
+        // Enhanced scoring algorithm
+        let donation_factor = if trust_score.donation_count > 100 { 100 } else { trust_score.donation_count };
+        let amount_factor = if trust_score.total_donated > 100000 { 100000 } else { trust_score.total_donated };
+        let consistency_factor = if trust_score.donation_count > 1 { 120u64 } else { 100u64 };
+        let verification_bonus = match trust_score.verification_level {
+            0 => 0,
+            1 => 10,
+            2 => 20,
+            _ => 30,
+        };
+
+        // Calculate new score with peso amounts
+        let base_score = 50u64;
+        let donation_component = (25u64 * donation_factor as u64) / 100u64;
+        let amount_component = (20u64 * amount_factor) / 100000u64;
+        let consistency_component = (consistency_factor * 5u64) / 100u64;
+        
+        let new_score = base_score + donation_component + amount_component + consistency_component + verification_bonus as u64;
+        
+        // Apply penalties
+        let penalty = (trust_score.late_submissions * 10) + (trust_score.fraud_reports * 30);
+        let final_score = new_score.saturating_sub(penalty as u64);
+        
+        trust_score.score = if final_score > 100 { 100 } else { final_score as u32 };
+
+        env.storage().persistent().set(&DataKey::TrustScore(donor), &trust_score);
+        Ok(())
+    }
+
+    /// Report fraud
+    pub fn report_fraud(env: Env, reported_entity: Address, reporter: Address) -> Result<(), SaviaError> {
+        reporter.require_auth();
+        Self::require_not_blocked(&env, &reporter)?;
+
+        // Verify reporter has some trust score
+        let reporter_trust: TrustScore = env.storage().persistent().get(&DataKey::TrustScore(reporter.clone()))
+            .ok_or(SaviaError::TrustScoreNotFound)?;
+
+        if reporter_trust.score < 30 {
+            return Err(SaviaError::InsufficientTrustScore);
+        }
+
+        // Update reported entity's trust score
+        let mut fraud_reports = 0;
+        if let Some(mut trust_score) = env.storage().persistent().get::<DataKey, TrustScore>(&DataKey::TrustScore(reported_entity.clone())) {
+            trust_score.fraud_reports += 1;
+            trust_score.score = trust_score.score.saturating_sub(30);
+            fraud_reports = trust_score.fraud_reports;
+            env.storage().persistent().set(&DataKey::TrustScore(reported_entity.clone()), &trust_score);
+        }
+
+        env.events().publish(
+            (symbol_short!("fraud"), symbol_short!("reported"), reported_entity),
+            FraudReportedEvent { reporter, fraud_reports },
+        );
+        Ok(())
+    }
+
+    /// Process refund for donation (callable by the donor or any keeper)
+    ///
+    /// The donor gets their share of the escrow back; the platform fee taken
+    /// at donation time is not refunded.
+    pub fn process_refund(env: Env, donation_id: BytesN<32>) -> Result<u64, SaviaError> {
+        let mut donation: Donation = env.storage().persistent().get(&DataKey::Donation(donation_id.clone()))
+            .ok_or(SaviaError::DonationNotFound)?;
+
+        if donation.refunded {
+            return Ok(0); // Already refunded
+        }
+
+        // Only donations escrowed through `donate` carry a peso conversion;
+        // bare records never put tokens in escrow and cannot draw from it
+        let tx_id: BytesN<32> = env.storage().persistent().get(&DataKey::DonationConversion(donation_id.clone()))
+            .ok_or(SaviaError::RefundNotAvailable)?;
+
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(donation.campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        if !campaign.refunding {
+            return Err(SaviaError::RefundNotAvailable);
+        }
+
+        let current_time = env.ledger().timestamp();
+        let refund_deadline = campaign.refund_started_at + (30 * 24 * 60 * 60); // 30 days to claim
+
+        if current_time > refund_deadline {
+            return Err(SaviaError::RefundPeriodExpired);
+        }
+
+        // Pay back the donor's share of whatever was still in escrow
+        let refund_amount = Self::escrow_share(&env, &campaign, &donation)?;
+
+        let escrow: u64 = env.storage().persistent().get(&DataKey::CampaignEscrow(campaign.id.clone())).unwrap_or(0);
+        if refund_amount > escrow {
+            return Err(SaviaError::InsufficientFunds);
+        }
+
+        if refund_amount > 0 {
+            let donation_token: Address = env.storage().instance().get(&DataKey::DonationToken)
+                .ok_or(SaviaError::NotInitialized)?;
+            token::Client::new(&env, &donation_token).transfer(&env.current_contract_address(), &donation.donor, &(refund_amount as i128));
+        }
+        env.storage().persistent().set(&DataKey::CampaignEscrow(campaign.id.clone()), &(escrow - refund_amount));
+
+        // Mark donation as refunded
+        donation.refunded = true;
+        env.storage().persistent().set(&DataKey::Donation(donation_id.clone()), &donation);
+
+        campaign.current_amount = campaign.current_amount.saturating_sub(donation.amount);
+        campaign.peso_raised = campaign.peso_raised.saturating_sub(donation.peso_amount);
+        env.storage().persistent().set(&DataKey::Campaign(campaign.id.clone()), &campaign);
+
+        // Update EtherFuse transaction status
+        if let Some(mut etherfuse_tx) = env.storage().persistent().get::<DataKey, EtherFuseTransaction>(&DataKey::EtherFuseTransaction(tx_id.clone())) {
+            if etherfuse_tx.status.can_transition_to(&TransactionStatus::Refunded) {
+                etherfuse_tx.status = TransactionStatus::Refunded;
+                etherfuse_tx.settled_at = current_time;
+                env.storage().persistent().set(&DataKey::EtherFuseTransaction(tx_id), &etherfuse_tx);
+            }
+        }
+
+        Self::reverse_donation_totals(&env, &donation, &campaign.beneficiary);
+
+        env.events().publish(
+            (symbol_short!("refund"), symbol_short!("paid"), campaign.id),
+            RefundEvent { donation_id, donor: donation.donor, amount: refund_amount },
+        );
+        Ok(refund_amount)
+    }
+
+    /// Sweep escrow left unclaimed after the refund window closes (admin function)
+    pub fn sweep_unclaimed_refunds(env: Env, campaign_id: BytesN<32>, to: Address) -> Result<u64, SaviaError> {
+        Self::require_admin(&env)?;
+        let campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        if !campaign.refunding {
+            return Err(SaviaError::RefundNotAvailable);
+        }
+        if env.ledger().timestamp() <= campaign.refund_started_at + (30 * 24 * 60 * 60) {
+            return Err(SaviaError::RefundWindowOpen);
+        }
+
+        let escrow: u64 = env.storage().persistent().get(&DataKey::CampaignEscrow(campaign_id.clone())).unwrap_or(0);
+        if escrow > 0 {
+            let donation_token: Address = env.storage().instance().get(&DataKey::DonationToken)
+                .ok_or(SaviaError::NotInitialized)?;
+            token::Client::new(&env, &donation_token).transfer(&env.current_contract_address(), &to, &(escrow as i128));
+        }
+        env.storage().persistent().set(&DataKey::CampaignEscrow(campaign_id.clone()), &0u64);
+
+        env.events().publish(
+            (symbol_short!("refund"), symbol_short!("swept"), campaign_id),
+            RefundSweptEvent { to, amount: escrow },
+        );
+        Ok(escrow)
+    }
+
+    /// A donation's pro-rata share of the campaign escrow
+    ///
+    /// Once refunds start every donor is paid from the snapshot taken then;
+    /// before that the share is taken from what milestones have left.
+    fn escrow_share(env: &Env, campaign: &Campaign, donation: &Donation) -> Result<u64, SaviaError> {
+        let (escrow, raised) = if campaign.refunding {
+            let pool: RefundPool = env.storage().persistent().get(&DataKey::RefundPool(campaign.id.clone()))
+                .ok_or(SaviaError::RefundNotAvailable)?;
+            (pool.escrow_at_start, pool.raised_at_start)
+        } else {
+            let escrow: u64 = env.storage().persistent().get(&DataKey::CampaignEscrow(campaign.id.clone())).unwrap_or(0);
+            (escrow, campaign.current_amount)
+        };
+        if raised == 0 {
+            return Ok(0);
+        }
+        Ok(((donation.amount as u128 * escrow as u128) / raised as u128) as u64)
+    }
+
+    /// Take a reversed donation out of the donor's trust score, NFT, limit and AML totals
+    fn reverse_donation_totals(env: &Env, donation: &Donation, beneficiary: &Address) {
+        if let Some(mut trust_score) = env.storage().persistent().get::<DataKey, TrustScore>(&DataKey::TrustScore(donation.donor.clone())) {
+            trust_score.donation_count = trust_score.donation_count.saturating_sub(1);
+            trust_score.total_donated = trust_score.total_donated.saturating_sub(donation.peso_amount);
+            trust_score.last_updated = env.ledger().timestamp();
+            env.storage().persistent().set(&DataKey::TrustScore(donation.donor.clone()), &trust_score);
+        }
+
+        if donation.nft_minted {
+            let nft_key = Self::donor_nft_key(env, &donation.donor, &donation.campaign_id);
+            if let Some(mut nft) = env.storage().persistent().get::<DataKey, DynamicNFT>(&DataKey::DynamicNFT(nft_key.clone())) {
+                nft.total_donated = nft.total_donated.saturating_sub(donation.amount);
+                nft.donation_count = nft.donation_count.saturating_sub(1);
+                nft.growth_stage = Self::calculate_growth_stage(nft.total_donated);
+                nft.last_updated = env.ledger().timestamp();
+                env.storage().persistent().set(&DataKey::DynamicNFT(nft_key), &nft);
+            }
+        }
+
+        // Only a donation inside the donor's current limit period still counts there
+        let period_key = ComplianceKey::DonorPeriodTotal(donation.donor.clone());
+        if let Some(mut period) = env.storage().persistent().get::<ComplianceKey, DonorPeriodTotal>(&period_key) {
+            if donation.timestamp >= period.period_start {
+                period.total = period.total.saturating_sub(donation.gross_peso_amount);
+                env.storage().persistent().set(&period_key, &period);
+            }
+        }
+
+        let aml_key = ComplianceKey::AmlActivity(donation.donor.clone(), beneficiary.clone());
+        if let Some(activity) = env.storage().persistent().get::<ComplianceKey, Vec<AmlEntry>>(&aml_key) {
+            let mut remaining = Vec::new(env);
+            for entry in activity.iter() {
+                if entry.donation_id != donation.id {
+                    remaining.push_back(entry);
+                }
+            }
+            env.storage().persistent().set(&aml_key, &remaining);
+        }
+    }
+
+    /// amount * factor / 10000 for rates and fees scaled by 10000
+    fn scale(amount: u64, factor: u64) -> Result<u64, SaviaError> {
+        u64::try_from(amount as u128 * factor as u128 / 10000).map_err(|_| SaviaError::InvalidAmount)
+    }
+
+    /// Get campaign statistics
+    pub fn get_campaign_stats(env: Env, campaign_id: BytesN<32>) -> Result<Map<String, u64>, SaviaError> {
+        let campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        let mut stats = Map::new(&env);
+        stats.set(String::from_str(&env, "goal_amount"), campaign.goal_amount);
+        stats.set(String::from_str(&env, "current_amount"), campaign.current_amount);
+        stats.set(String::from_str(&env, "peso_goal"), Self::scale(campaign.goal_amount, Self::current_peso_rate(&env).rate)?);
+        stats.set(String::from_str(&env, "peso_raised"), campaign.peso_raised);
+        stats.set(String::from_str(&env, "days_remaining"), 
+            if env.ledger().timestamp() < campaign.end_time { 
+                (campaign.end_time - env.ledger().timestamp()) / (24 * 60 * 60) 
+            } else { 0 }
+        );
+        stats.set(String::from_str(&env, "trust_score"), campaign.trust_score as u64);
+        stats.set(String::from_str(&env, "kyc_verified"), if campaign.kyc_verified { 1 } else { 0 });
+        stats.set(String::from_str(&env, "medical_verified"), if campaign.medical_docs_verified { 1 } else { 0 });
+        stats.set(String::from_str(&env, "funds_locked"), if campaign.funds_locked { 1 } else { 0 });
+
+        Ok(stats)
+    }
+
+    /// Get donor dashboard data
+    pub fn get_donor_dashboard(env: Env, donor: Address) -> Result<Map<String, u64>, SaviaError> {
+        let mut dashboard = Map::new(&env);
+
+        // Get trust score
+        if let Some(trust_score) = env.storage().persistent().get::<DataKey, TrustScore>(&DataKey::TrustScore(donor.clone())) {
+            dashboard.set(String::from_str(&env, "trust_score"), trust_score.score as u64);
+            dashboard.set(String::from_str(&env, "total_donated"), trust_score.total_donated);
+            dashboard.set(String::from_str(&env, "donation_count"), trust_score.donation_count as u64);
+        }
+
+        // Get KYC status
+        if let Some(kyc_record) = env.storage().persistent().get::<DataKey, KYCRecord>(&DataKey::KYCRecord(donor.clone())) {
+            let kyc_level = match kyc_record.verification_level {
+                KYCLevel::Unverified => 0,
+                KYCLevel::BasicVerified => 1,
+                KYCLevel::MedicalVerified => 2,
+                KYCLevel::FullyVerified => 3,
+            };
+            dashboard.set(String::from_str(&env, "kyc_level"), kyc_level);
+            dashboard.set(String::from_str(&env, "kyc_expires"), kyc_record.expires_at);
+        }
+
+        Ok(dashboard)
+    }
+
+    /// Verify campaign (admin function)
+    pub fn verify_campaign(
+        env: Env,
+        campaign_id: BytesN<32>,
+        trust_score: u32,
+        verifier: Address,
+    ) -> Result<(), SaviaError> {
+        verifier.require_auth();
+
+        // Check if verifier is authorized
+        let verifiers: Vec<Address> = env.storage().instance().get(&DataKey::KYCVerifiers).unwrap_or(Vec::new(&env));
+        if !verifiers.contains(&verifier) {
+            return Err(SaviaError::NotAuthorized);
+        }
+
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        campaign.verified = true;
+        campaign.trust_score = trust_score;
+
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id), &campaign);
+        Ok(())
+    }
+
+    /// Get current peso exchange rate
+    pub fn get_peso_exchange_rate(env: Env) -> u64 {
+        Self::current_peso_rate(&env).rate
+    }
+
+    /// Get current peso exchange rate with its age and source
+    pub fn get_peso_rate_quote(env: Env) -> PesoRateQuote {
+        Self::current_peso_rate(&env)
+    }
+
+    /// Read XLM/MXN from the price feed, falling back to the stored rate
+    fn current_peso_rate(env: &Env) -> PesoRateQuote {
+        if let Some(config) = env.storage().instance().get::<DataKey, PriceFeedConfig>(&DataKey::PriceFeed) {
+            if let Some(quote) = Self::price_feed_rate(env, &config) {
+                return quote;
+            }
+        }
+
+        PesoRateQuote {
+            rate: env.storage().instance().get(&DataKey::PesoExchangeRate).unwrap_or(180000), // Default ~18 pesos per XLM
+            updated_at: env.storage().instance().get(&DataKey::PesoRateUpdatedAt).unwrap_or(0),
+            from_price_feed: false,
+        }
+    }
+
+    /// Derive XLM/MXN from two feed prices; None if either is missing, stale or unusable
+    fn price_feed_rate(env: &Env, config: &PriceFeedConfig) -> Option<PesoRateQuote> {
+        let feed = PriceFeedClient::new(env, &config.feed);
+        let xlm_price = feed.try_lastprice(&config.xlm_asset).ok()?.ok()??;
+        let mxn_price = feed.try_lastprice(&config.mxn_asset).ok()?.ok()??;
+
+        let current_time = env.ledger().timestamp();
+        let updated_at = xlm_price.timestamp.min(mxn_price.timestamp);
+        if xlm_price.price <= 0 || mxn_price.price <= 0 || current_time > updated_at + config.max_age {
+            return None;
+        }
+
+        // Feed decimals cancel out in the ratio
+        let rate = xlm_price.price.checked_mul(10000)? / mxn_price.price;
+        if rate <= 0 || rate > u64::MAX as i128 {
+            return None;
+        }
+
+        Some(PesoRateQuote {
+            rate: rate as u64,
+            updated_at,
+            from_price_feed: true,
+        })
+    }
+
+    /// Read XLM/MXN from a SEP-40 price feed (admin function)
+    pub fn set_price_feed(env: Env, config: PriceFeedConfig) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        if config.max_age == 0 || config.xlm_asset == config.mxn_asset {
+            return Err(SaviaError::InvalidExchangeRate);
+        }
+        env.storage().instance().set(&DataKey::PriceFeed, &config);
+        Ok(())
+    }
+
+    /// Stop reading from the price feed and use the stored rate (admin function)
+    pub fn clear_price_feed(env: Env) -> Result<(), SaviaError> {
+        Self::require_admin(&env)?;
+        env.storage().instance().remove(&DataKey::PriceFeed);
+        Ok(())
+    }
+
+    /// Get the configured price feed
+    pub fn get_price_feed(env: Env) -> Option<PriceFeedConfig> {
+        env.storage().instance().get(&DataKey::PriceFeed)
+    }
+
+    /// Emergency pause campaign (admin function)
+    pub fn emergency_pause_campaign(env: Env, campaign_id: BytesN<32>) -> Result<(), SaviaError> {
+        let admin = Self::require_admin(&env)?;
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        campaign.funds_locked = true;
+        campaign.lock_reason = LockReason::EmergencyPause;
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+
+        env.events().publish((symbol_short!("campaign"), symbol_short!("paused"), campaign_id), admin);
+        Ok(())
+    }
+
+    /// Resume campaign after emergency pause (admin function)
+    pub fn resume_campaign(env: Env, campaign_id: BytesN<32>) -> Result<(), SaviaError> {
+        let admin = Self::require_admin(&env)?;
+        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
+            .ok_or(SaviaError::CampaignNotFound)?;
+
+        // Only resume if medical docs are verified and within deadline, and the beneficiary's KYC is current
+        if campaign.refunding {
+            return Err(SaviaError::CampaignEnded);
+        }
+        if !campaign.medical_docs_verified {
+            return Err(SaviaError::NotApproved);
+        }
+        if env.ledger().timestamp() > campaign.proof_deadline {
+            return Err(SaviaError::ProofDeadlineExceeded);
+        }
+        if !Self::kyc_is_current(&env, &campaign.beneficiary) {
+            return Err(SaviaError::KYCExpired);
+        }
+
+        campaign.funds_locked = false;
+        campaign.lock_reason = LockReason::Unlocked;
+        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+
+        env.events().publish((symbol_short!("campaign"), symbol_short!("resumed"), campaign_id), admin);
+        Ok(())
+    }
+
+    /// Mint a new dynamic NFT for a donor (admin function)
+    pub fn mint(env: Env, owner: Address, metadata_json: String, stage_id: u32) -> Result<BytesN<32>, SaviaError> {
+        Self::require_admin(&env)?;
+
+        let counter: u64 = env.storage().instance().get(&DataKey::NFTCounter).unwrap_or(0);
+        let new_counter = counter + 1;
+        env.storage().instance().set(&DataKey::NFTCounter, &new_counter);
+
+        let mut hash_input = Bytes::new(&env);
+        hash_input.append(&owner.clone().to_xdr(&env));
+        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
+        let nft_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+
+        let growth_stage = match stage_id {
+            0 => TreeGrowthStage::PreSeed,
+            1 => TreeGrowthStage::Seed,
+            2 => TreeGrowthStage::Sprout,
+            3 => TreeGrowthStage::Bloom,
+            4 => TreeGrowthStage::Blossom,
+            5 => TreeGrowthStage::Tree,
+            _ => TreeGrowthStage::SaviaTree,
+        };
+
+        let current_time = env.ledger().timestamp();
+        let zero: [u8; 32] = [0u8; 32];
+        let nft = DynamicNFT {
+            id: nft_id.clone(),
+            owner: owner.clone(),
+            campaign_id: BytesN::from_array(&env, &zero),
+            tree_level: stage_id,
+            total_donated: 0,
+            donation_count: 0,
+            created_at: current_time,
+            last_updated: current_time,
+            metadata_uri: metadata_json,
+            growth_stage: growth_stage.clone(),
+            special_achievements: Vec::new(&env),
+        };
+
+        env.storage().persistent().set(&DataKey::DynamicNFT(nft_id.clone()), &nft);
+
+        let mut owner_nfts: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::OwnerNFTs(owner.clone())).unwrap_or(Vec::new(&env));
+        owner_nfts.push_back(nft_id.clone());
+        env.storage().persistent().set(&DataKey::OwnerNFTs(owner.clone()), &owner_nfts);
+
+        env.events().publish(
+            (symbol_short!("nft"), symbol_short!("minted"), owner),
+            NFTMintedEvent { nft_id: nft_id.clone(), campaign_id: nft.campaign_id, growth_stage },
+        );
+
+        Ok(nft_id)
+    }
+
+    /// Record an on-chain donation entry (donor function)
+    ///
+    /// Recorded entries move no tokens, so they are never refundable and
+    /// stay out of the campaign and donor listings.
+    pub fn record_donation(env: Env, campaign_id: BytesN<32>, donor: Address, amount: u64) -> Result<BytesN<32>, SaviaError> {
+        donor.require_auth();
+
+        let counter: u64 = env.storage().instance().get(&DataKey::DonationCounter).unwrap_or(0);
+        let new_counter = counter + 1;
+        env.storage().instance().set(&DataKey::DonationCounter, &new_counter);
 
-        // Generate campaign ID
-        let current_time = env.ledger().timestamp();
         let mut hash_input = Bytes::new(&env);
-        
-        let beneficiary_bytes = beneficiary.clone().to_xdr(&env);
-        let title_bytes = title.to_val().to_xdr(&env);
-               
-        hash_input.append(&beneficiary_bytes);
-        hash_input.append(&title_bytes);
-        hash_input.append(&Bytes::from_slice(&env, &goal_amount.to_be_bytes()));
-        hash_input.append(&Bytes::from_slice(&env, &current_time.to_be_bytes()));
+        hash_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
+        hash_input.append(&donor.clone().to_xdr(&env));
+        hash_input.append(&Bytes::from_slice(&env, &amount.to_be_bytes()));
         hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
-        
-        let campaign_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+        let donation_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+
+        let donation = Donation {
+            id: donation_id.clone(),
+            campaign_id,
+            donor,
+            amount,
+            peso_amount: 0,
+            gross_peso_amount: 0,
+            exchange_rate: 0,
+            timestamp: env.ledger().timestamp(),
+            nft_minted: false,
+            anonymous: false,
+            refunded: false,
+            etherfuse_tx_id: None,
+        };
+
+        env.storage().persistent().set(&DataKey::Donation(donation_id.clone()), &donation);
+        Ok(donation_id)
+    }
+
+    /// Append a donation to the per-campaign and per-donor indexes
+    fn index_donation(env: &Env, donation: &Donation) {
+        let campaign_key = DataKey::CampaignDonations(donation.campaign_id.clone());
+        let mut campaign_donations: Vec<BytesN<32>> = env.storage().persistent().get(&campaign_key).unwrap_or(Vec::new(env));
+        campaign_donations.push_back(donation.id.clone());
+        env.storage().persistent().set(&campaign_key, &campaign_donations);
+
+        // Anonymous donations are kept out of the donor index so they cannot
+        // be traced back to an address
+        if !donation.anonymous {
+            let donor_key = DataKey::DonorDonations(donation.donor.clone());
+            let mut donor_donations: Vec<BytesN<32>> = env.storage().persistent().get(&donor_key).unwrap_or(Vec::new(env));
+            donor_donations.push_back(donation.id.clone());
+            env.storage().persistent().set(&donor_key, &donor_donations);
+        }
+    }
+
+    /// Load a page of donations from an index
+    fn donation_page(env: &Env, index: Vec<BytesN<32>>, start: u32, limit: u32) -> Vec<DonationListing> {
+        let mut page = Vec::new(env);
+        let end = start.saturating_add(limit.min(MAX_PAGE_SIZE)).min(index.len());
+        for i in start..end {
+            let donation_id = index.get_unchecked(i);
+            if let Some(donation) = env.storage().persistent().get::<DataKey, Donation>(&DataKey::Donation(donation_id)) {
+                page.push_back(DonationListing {
+                    id: donation.id,
+                    campaign_id: donation.campaign_id,
+                    donor: if donation.anonymous { None } else { Some(donation.donor) },
+                    amount: donation.amount,
+                    peso_amount: donation.peso_amount,
+                    exchange_rate: donation.exchange_rate,
+                    timestamp: donation.timestamp,
+                    anonymous: donation.anonymous,
+                    refunded: donation.refunded,
+                });
+            }
+        }
+        page
+    }
+
+    /// List a campaign's donations, oldest first
+    pub fn list_campaign_donations(env: Env, campaign_id: BytesN<32>, start: u32, limit: u32) -> Vec<DonationListing> {
+        let index: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::CampaignDonations(campaign_id)).unwrap_or(Vec::new(&env));
+        Self::donation_page(&env, index, start, limit)
+    }
+
+    /// List a donor's non-anonymous donations, oldest first
+    pub fn list_donor_donations(env: Env, donor: Address, start: u32, limit: u32) -> Vec<DonationListing> {
+        let index: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::DonorDonations(donor)).unwrap_or(Vec::new(&env));
+        Self::donation_page(&env, index, start, limit)
+    }
+
+    /// Get the number of donations made to a campaign
+    pub fn get_campaign_donation_count(env: Env, campaign_id: BytesN<32>) -> u32 {
+        let index: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::CampaignDonations(campaign_id)).unwrap_or(Vec::new(&env));
+        index.len()
+    }
+
+    /// Get the number of non-anonymous donations made by a donor
+    pub fn get_donor_donation_count(env: Env, donor: Address) -> u32 {
+        let index: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::DonorDonations(donor)).unwrap_or(Vec::new(&env));
+        index.len()
+    }
+
+    /// Return the number of NFTs owned by an address
+    pub fn balance_of(env: Env, owner: Address) -> u32 {
+        let owner_nfts: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::OwnerNFTs(owner)).unwrap_or(Vec::new(&env));
+        owner_nfts.len()
+    }
+
+    /// Return the list of NFT IDs owned by an address
+    pub fn tokens_for_owner(env: Env, owner: Address) -> Vec<BytesN<32>> {
+        env.storage().persistent().get(&DataKey::OwnerNFTs(owner)).unwrap_or(Vec::new(&env))
+    }
+}
+
+// ========== TESTS ==========
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke},
+        token::{StellarAssetClient, TokenClient},
+        Address, Env, IntoVal, Symbol, TryFromVal, Val,
+    };
+
+    fn setup(env: &Env, client: &SaviaContractClient) -> (Address, Address) {
+        env.mock_all_auths();
+        let admin = client.get_admin().unwrap();
+        let token = env.register_stellar_asset_contract_v2(admin.clone()).address();
+        client.initialize(&token, &200, &String::from_str(env, "etherfuse_config"), &180000);
+        (admin, token)
+    }
+
+    fn fund(env: &Env, token: &Address, to: &Address, amount: i128) {
+        StellarAssetClient::new(env, token).mint(to, &amount);
+    }
+
+    // Fixed test salt, distinct for each KYC field
+    fn salt(env: &Env, field: KYCField) -> BytesN<32> {
+        BytesN::from_array(env, &[field as u8 + 1; 32])
+    }
+
+    fn commit(env: &Env, field: KYCField, value: &str) -> BytesN<32> {
+        commit_with_salt(env, field.clone(), &salt(env, field), value)
+    }
+
+    fn commit_with_salt(env: &Env, field: KYCField, salt: &BytesN<32>, value: &str) -> BytesN<32> {
+        let mut preimage = Bytes::from_slice(env, &(field as u32).to_be_bytes());
+        preimage.append(&Bytes::from_slice(env, salt.to_array().as_slice()));
+        preimage.append(&Bytes::from_slice(env, value.as_bytes()));
+        env.crypto().sha256(&preimage).into()
+    }
+
+    fn register_beneficiary(env: &Env, client: &SaviaContractClient, addr: &Address) {
+        client.register_kyc(
+            addr,
+            &commit(env, KYCField::CURP, "GOMC900101HDFRRL07"),
+            &commit(env, KYCField::FullName, "Maria González"),
+            &commit(env, KYCField::Phone, "5551234567"),
+            &commit(env, KYCField::Email, "maria@example.com"),
+            &commit(env, KYCField::Address, "Guadalajara"),
+            &None,
+            &None,
+        );
+        let kyc_verifier = Address::generate(env);
+        client.add_kyc_verifier(&kyc_verifier);
+        client.attest_kyc_field(&kyc_verifier, addr, &KYCField::CURP, &commit(env, KYCField::CURP, "GOMC900101HDFRRL07"));
+        client.attest_kyc_field(&kyc_verifier, addr, &KYCField::Phone, &commit(env, KYCField::Phone, "5551234567"));
+        client.verify_kyc(&kyc_verifier, addr, &KYCLevel::BasicVerified);
+    }
+
+    #[contract]
+    pub struct MockPriceFeed;
+
+    #[contractimpl]
+    impl MockPriceFeed {
+        pub fn set_price(env: Env, asset: Asset, price: i128, timestamp: u64) {
+            env.storage().instance().set(&asset, &PriceData { price, timestamp });
+        }
+
+        pub fn lastprice(env: Env, asset: Asset) -> Option<PriceData> {
+            env.storage().instance().get(&asset)
+        }
+    }
+
+    #[contract]
+    pub struct MockScreening;
+
+    #[contractimpl]
+    impl MockScreening {
+        pub fn set_listed(env: Env, address: Address, listed: bool) {
+            env.storage().instance().set(&address, &listed);
+        }
+
+        pub fn is_listed(env: Env, address: Address) -> bool {
+            env.storage().instance().get(&address).unwrap_or(false)
+        }
+    }
+
+    // Events published by the Savia contract during the last invocation
+    fn savia_events(env: &Env, contract_id: &Address) -> Vec<(Vec<Val>, Val)> {
+        let mut events = Vec::new(env);
+        for (emitter, topics, data) in env.events().all().iter() {
+            if emitter == *contract_id {
+                events.push_back((topics, data));
+            }
+        }
+        events
+    }
+
+    fn find_event<T: TryFromVal<Env, Val>>(env: &Env, contract_id: &Address, area: &str, action: &str) -> Option<T> {
+        savia_events(env, contract_id).iter().find_map(|(topics, data)| {
+            let topic_area = Symbol::try_from_val(env, &topics.get(0)?).ok()?;
+            let topic_action = Symbol::try_from_val(env, &topics.get(1)?).ok()?;
+            if topic_area == Symbol::new(env, area) && topic_action == Symbol::new(env, action) {
+                T::try_from_val(env, &data).ok()
+            } else {
+                None
+            }
+        })
+    }
+
+    fn create_test_campaign(env: &Env, client: &SaviaContractClient, beneficiary: &Address) -> BytesN<32> {
+        client.create_campaign(
+            beneficiary,
+            &String::from_str(env, "Tratamiento"),
+            &String::from_str(env, "Ayuda médica"),
+            &String::from_str(env, "Cirugía"),
+            &500000,
+            &60,
+            &String::from_str(env, "Salud"),
+            &String::from_str(env, "Mexico City"),
+            &String::from_str(env, "ETF_ACCOUNT_123"),
+        )
+    }
+
+    #[test]
+    fn test_initialize_enhanced_contract() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (admin, token) = setup(&env, &client);
+
+        assert_eq!(client.get_admin(), Some(admin));
+        assert_eq!(client.get_donation_token(), Some(token));
+        assert_eq!(client.get_peso_exchange_rate(), 180000);
+    }
+
+    #[test]
+    fn test_initialize_only_once() {
+        let env = Env::default();
+        let admin = Address::generate(&env);
+        let contract_id = env.register(SaviaContract, (admin.clone(),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let attacker = Address::generate(&env);
+        let token = env.register_stellar_asset_contract_v2(attacker.clone()).address();
+
+        // The admin is fixed at deployment, so nobody else can initialize first
+        let result = client
+            .mock_auths(&[MockAuth {
+                address: &attacker,
+                invoke: &MockAuthInvoke {
+                    contract: &contract_id,
+                    fn_name: "initialize",
+                    args: (&token, 0u64, String::from_str(&env, "etherfuse_config"), 1u64).into_val(&env),
+                    sub_invokes: &[],
+                },
+            }])
+            .try_initialize(&token, &0, &String::from_str(&env, "etherfuse_config"), &1);
+        assert!(result.is_err());
+        assert_eq!(client.get_donation_token(), None);
+
+        setup(&env, &client);
+        let result = client.try_initialize(&token, &0, &String::from_str(&env, "etherfuse_config"), &1);
+        assert_eq!(
+            result,
+            Err(Ok(SaviaError::AlreadyInitialized))
+        );
+        assert_eq!(client.get_admin(), Some(admin));
+    }
+
+    #[test]
+    fn test_admin_functions_require_admin_auth() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (admin, _) = setup(&env, &client);
+
+        client.update_peso_exchange_rate(&190000);
+        assert_eq!(env.auths()[0].0, admin);
+
+        // Drop the blanket mock so the admin signature is actually checked
+        env.set_auths(&[]);
+        let verifier = Address::generate(&env);
+        assert!(client.try_update_peso_exchange_rate(&200000).is_err());
+        assert!(client.try_add_kyc_verifier(&verifier).is_err());
+        assert!(client.try_add_medical_verifier(&verifier).is_err());
+        assert_eq!(client.get_peso_exchange_rate(), 190000);
+    }
+
+    #[test]
+    fn test_kyc_registration() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
+
+        let user = Address::generate(&env);
+        client.register_kyc(
+            &user,
+            &commit(&env, KYCField::CURP, "GOMC900101HDFRRL07"),
+            &commit(&env, KYCField::FullName, "Juan Pérez"),
+            &commit(&env, KYCField::Phone, "5551234567"),
+            &commit(&env, KYCField::Email, "juan@example.com"),
+            &commit(&env, KYCField::Address, "Mexico City"),
+            &None,
+            &None,
+        );
+
+        let kyc_record = client.get_kyc_record(&user);
+        assert!(kyc_record.is_some());
+    }
+
+    #[test]
+    fn test_enhanced_campaign_creation() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
+
+        let beneficiary = Address::generate(&env);
+        register_beneficiary(&env, &client, &beneficiary);
+
+        let campaign_id = client.create_campaign(
+            &beneficiary,
+            &String::from_str(&env, "Tratamiento de Cáncer"),
+            &String::from_str(&env, "Necesito ayuda para mi tratamiento"),
+            &String::from_str(&env, "Cáncer de mama"),
+            &500000,
+            &60,
+            &String::from_str(&env, "Salud"),
+            &String::from_str(&env, "Guadalajara"),
+            &String::from_str(&env, "ETF_ACCOUNT_123"),
+        );
+
+        let campaign = client.get_campaign(&campaign_id);
+        assert!(campaign.is_some());
+        assert!(campaign.unwrap().kyc_verified);
+    }
+
+    #[test]
+    fn test_donation_with_peso_conversion() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 10_000_000);
+
+        register_beneficiary(&env, &client, &beneficiary);
+
+        let campaign_id = client.create_campaign(
+            &beneficiary,
+            &String::from_str(&env, "Tratamiento Médico"),
+            &String::from_str(&env, "Ayuda médica urgente"),
+            &String::from_str(&env, "Cirugía"),
+            &500000,
+            &60,
+            &String::from_str(&env, "Salud"),
+            &String::from_str(&env, "Mexico City"),
+            &String::from_str(&env, "ETF_ACCOUNT_123"),
+        );
+
+        client.donate(
+            &campaign_id,
+            &donor,
+            &10000000,
+            &false,
+            &true,
+            &None,
+            &None,
+        );
+
+        let campaigns = client.get_campaign(&campaign_id);
+        assert!(campaigns.is_some());
+        assert!(campaigns.unwrap().current_amount > 0);
+    }
+
+    #[test]
+    fn test_dynamic_nft_growth() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 200_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+
+        let campaign_id = client.create_campaign(
+            &beneficiary,
+            &String::from_str(&env, "Tratamiento"),
+            &String::from_str(&env, "Ayuda médica"),
+            &String::from_str(&env, "Cirugía"),
+            &500000,
+            &60,
+            &String::from_str(&env, "Salud"),
+            &String::from_str(&env, "Mexico City"),
+            &String::from_str(&env, "ETF_ACCOUNT_123"),
+        );
 
-        let end_time = current_time + (duration_days * 24 * 60 * 60);
-        let proof_deadline = current_time + (30 * 24 * 60 * 60); // 30 days for first proof
+        client.donate(&campaign_id, &donor, &50000000, &false, &true, &None, &None);
 
-        let campaign = Campaign {
-            id: campaign_id.clone(),
-            title,
-            description,
-            beneficiary,
-            goal_amount,
-            current_amount: 0,
-            start_time: current_time,
-            end_time,
-            verified: false,
-            trust_score: 0,
-            category,
-            location,
-            medical_condition,
-            kyc_verified: true,
-            medical_docs_verified: false,
-            last_proof_submitted: 0,
-            proof_deadline,
-            funds_locked: false,
-            etherfuse_account,
-            peso_exchange_rate: peso_rate,
-        };
+        let nft = client.get_donor_nft(&donor, &campaign_id);
+        assert!(nft.is_some());
+        let nft_data = nft.unwrap();
+        assert_eq!(nft_data.growth_stage, TreeGrowthStage::PreSeed);
 
-        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
-        Ok(campaign_id)
-    }
+        client.donate(&campaign_id, &donor, &150000000, &false, &true, &None, &None);
 
-    /// Submit medical documentation
-    pub fn submit_medical_documentation(
-        env: Env,
-        campaign_id: BytesN<32>,
-        document_type: MedicalDocType,
-        document_url: String,
-        notes: String,
-    ) -> Result<BytesN<32>, soroban_sdk::Error> {
-        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+        let updated_nft = client.get_donor_nft(&donor, &campaign_id);
+        assert!(updated_nft.is_some());
+        let updated_nft_data = updated_nft.unwrap();
+        assert_eq!(updated_nft_data.growth_stage, TreeGrowthStage::Seed);
+        assert_eq!(updated_nft_data.donation_count, 2);
+    }
 
-        // Get and increment medical doc counter
-        let counter: u64 = env.storage().instance().get(&DataKey::MedicalDocCounter).unwrap_or(0);
-        let new_counter = counter + 1;
-        env.storage().instance().set(&DataKey::MedicalDocCounter, &new_counter);
+    #[test]
+    fn test_medical_documentation_flow() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
 
-        // Generate document hash from URL and content
-        let mut hash_input = Bytes::new(&env);
-        hash_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
-        hash_input.append(&document_url.to_val().to_xdr(&env));
-        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
-        
-        let document_hash: BytesN<32> = env.crypto().sha256(&hash_input).into();
+        let beneficiary = Address::generate(&env);
+        let verifier = Address::generate(&env);
 
-        let current_time = env.ledger().timestamp();
-        let expiry_date = current_time + (90 * 24 * 60 * 60); // 90 days validity
+        client.add_medical_verifier(&verifier);
+        register_beneficiary(&env, &client, &beneficiary);
 
-        let medical_doc = MedicalDocumentation {
-            campaign_id: campaign_id.clone(),
-            document_type,
-            document_hash: document_hash.clone(),
-            document_url,
-            submitted_at: current_time,
-            verified_by: None,
-            verification_status: DocumentStatus::Pending,
-            expiry_date,
-            notes,
-        };
+        let campaign_id = client.create_campaign(
+            &beneficiary,
+            &String::from_str(&env, "Tratamiento"),
+            &String::from_str(&env, "Ayuda médica"),
+            &String::from_str(&env, "Cáncer"),
+            &500000,
+            &60,
+            &String::from_str(&env, "Salud"),
+            &String::from_str(&env, "Mexico City"),
+            &String::from_str(&env, "ETF_ACCOUNT_123"),
+        );
 
-        // Update campaign
-        campaign.last_proof_submitted = current_time;
-        campaign.proof_deadline = current_time + (30 * 24 * 60 * 60); // Reset 30-day deadline
-        campaign.funds_locked = false;
+        let doc_hash = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::MedicalDiagnosis,
+            &String::from_str(&env, "https://example.com/medical-report.pdf"),
+            &String::from_str(&env, "Diagnóstico médico oficial"),
+        );
 
-        env.storage().persistent().set(&DataKey::Campaign(campaign_id), &campaign);
-        env.storage().persistent().set(&DataKey::MedicalDoc(document_hash.clone()), &medical_doc);
+        client.verify_medical_documentation(&doc_hash, &verifier, &true);
 
-        Ok(document_hash)
+        let campaign = client.get_campaign(&campaign_id);
+        assert!(campaign.is_some());
+        assert!(campaign.unwrap().medical_docs_verified);
     }
 
-    /// Verify medical documentation (admin function)
-    pub fn verify_medical_documentation(
-        env: Env,
-        document_hash: BytesN<32>,
-        verifier: Address,
-        approved: bool,
-    ) -> Result<(), soroban_sdk::Error> {
-        // Check if verifier is authorized
-        let verifiers: Vec<Address> = env.storage().instance().get(&DataKey::MedicalVerifiers).unwrap_or(Vec::new(&env));
-        if !verifiers.contains(&verifier) {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::NotAuthorized as u32));
-        }
+    #[test]
+    fn test_donation_requires_donor_auth() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
 
-        let mut medical_doc: MedicalDocumentation = env.storage().persistent().get(&DataKey::MedicalDoc(document_hash.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::InvalidMedicalDoc as u32))?;
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let impostor = Address::generate(&env);
+        fund(&env, &token, &donor, 10_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+
+        // A signature from someone other than the donor is not enough
+        let result = client
+            .mock_auths(&[MockAuth {
+                address: &impostor,
+                invoke: &MockAuthInvoke {
+                    contract: &contract_id,
+                    fn_name: "donate",
+                    args: (&campaign_id, &donor, 10_000_000u64, false, false, None::<u64>, None::<u64>).into_val(&env),
+                    sub_invokes: &[],
+                },
+            }])
+            .try_donate(&campaign_id, &donor, &10_000_000, &false, &false, &None, &None);
+        assert!(result.is_err());
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().current_amount, 0);
+
+        // Nor can anyone record donations in the donor's name
+        let result = client
+            .mock_auths(&[MockAuth {
+                address: &impostor,
+                invoke: &MockAuthInvoke {
+                    contract: &contract_id,
+                    fn_name: "record_donation",
+                    args: (&campaign_id, &donor, 1_000u64).into_val(&env),
+                    sub_invokes: &[],
+                },
+            }])
+            .try_record_donation(&campaign_id, &donor, &1_000);
+        assert!(result.is_err());
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &donor,
+                invoke: &MockAuthInvoke {
+                    contract: &contract_id,
+                    fn_name: "donate",
+                    args: (&campaign_id, &donor, 10_000_000u64, false, false, None::<u64>, None::<u64>).into_val(&env),
+                    sub_invokes: &[MockAuthInvoke {
+                        contract: &token,
+                        fn_name: "transfer",
+                        args: (&donor, &contract_id, 10_000_000i128).into_val(&env),
+                        sub_invokes: &[],
+                    }],
+                },
+            }])
+            .donate(&campaign_id, &donor, &10_000_000, &false, &false, &None, &None);
+        assert!(client.get_campaign(&campaign_id).unwrap().current_amount > 0);
+    }
 
-        medical_doc.verified_by = Some(verifier);
-        medical_doc.verification_status = if approved { DocumentStatus::Verified } else { DocumentStatus::Rejected };
+    #[test]
+    fn test_campaign_and_kyc_require_owner_auth() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
 
-        // Update campaign verification status
-        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(medical_doc.campaign_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+        let beneficiary = Address::generate(&env);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        assert_eq!(env.auths()[0].0, beneficiary);
+
+        env.set_auths(&[]);
+        let other = Address::generate(&env);
+        assert!(client
+            .try_register_kyc(
+                &other,
+                &commit(&env, KYCField::CURP, "GOMC900101HDFRRL07"),
+                &commit(&env, KYCField::FullName, "Juan Pérez"),
+                &commit(&env, KYCField::Phone, "5551234567"),
+                &commit(&env, KYCField::Email, "juan@example.com"),
+                &commit(&env, KYCField::Address, "Mexico City"),
+                &None,
+                &None,
+            )
+            .is_err());
+        assert!(client.get_kyc_record(&other).is_none());
+
+        assert!(client
+            .try_create_campaign(
+                &beneficiary,
+                &String::from_str(&env, "Tratamiento"),
+                &String::from_str(&env, "Ayuda médica"),
+                &String::from_str(&env, "Cirugía"),
+                &500000,
+                &60,
+                &String::from_str(&env, "Salud"),
+                &String::from_str(&env, "Mexico City"),
+                &String::from_str(&env, "ETF_ACCOUNT_123"),
+            )
+            .is_err());
+
+        assert!(client
+            .try_submit_medical_documentation(
+                &campaign_id,
+                &MedicalDocType::MedicalDiagnosis,
+                &String::from_str(&env, "https://example.com/medical-report.pdf"),
+                &String::from_str(&env, "Diagnóstico médico oficial"),
+            )
+            .is_err());
+    }
 
-        if approved {
-            campaign.medical_docs_verified = true;
-            campaign.verified = true;
-        }
+    #[test]
+    fn test_verifiers_and_reporters_must_sign() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
 
-        env.storage().persistent().set(&DataKey::MedicalDoc(document_hash), &medical_doc);
-        env.storage().persistent().set(&DataKey::Campaign(medical_doc.campaign_id), &campaign);
+        let beneficiary = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        let reporter = Address::generate(&env);
+        client.add_medical_verifier(&verifier);
+        client.add_kyc_verifier(&verifier);
+        client.initialize_trust_score(&reporter);
+        client.initialize_trust_score(&beneficiary);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let doc_hash = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::MedicalDiagnosis,
+            &String::from_str(&env, "https://example.com/medical-report.pdf"),
+            &String::from_str(&env, "Diagnóstico médico oficial"),
+        );
 
-        Ok(())
+        env.set_auths(&[]);
+        assert!(client.try_verify_medical_documentation(&doc_hash, &verifier, &true).is_err());
+        assert!(client.try_verify_campaign(&campaign_id, &80, &verifier).is_err());
+        assert!(client.try_report_fraud(&beneficiary, &reporter).is_err());
+
+        let campaign = client.get_campaign(&campaign_id).unwrap();
+        assert!(!campaign.verified);
+        assert!(!campaign.medical_docs_verified);
+        assert_eq!(client.get_trust_score(&beneficiary).unwrap().fraud_reports, 0);
+
+        env.mock_all_auths();
+        client.verify_campaign(&campaign_id, &80, &verifier);
+        assert_eq!(env.auths()[0].0, verifier);
+        client.report_fraud(&beneficiary, &reporter);
+        assert_eq!(env.auths()[0].0, reporter);
     }
 
-    /// Process donation with peso conversion and dynamic NFT
-    pub fn donate(
-        env: Env,
-        campaign_id: BytesN<32>,
-        donor: Address,
-        xlm_amount: u64,
-        anonymous: bool,
-        mint_nft: bool,
-    ) -> Result<BytesN<32>, soroban_sdk::Error> {
-        // Validate campaign exists and is active
-        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+    #[test]
+    fn test_donation_escrows_tokens_and_fee() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-        let current_time = env.ledger().timestamp();
-        
-        // Check if campaign has ended
-        if current_time > campaign.end_time {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::CampaignEnded as u32));
-        }
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 100_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
 
-        // Check if funds are locked due to missing documentation
-        if campaign.funds_locked {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::FundsLocked as u32));
-        }
+        client.donate(&campaign_id, &donor, &100_000_000, &false, &false, &None, &None);
 
-        // Check if medical documentation is expired
-        if campaign.last_proof_submitted > 0 && (current_time > campaign.proof_deadline) {
-            campaign.funds_locked = true;
-            env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::ProofDeadlineExceeded as u32));
-        }
+        // 2% platform fee is kept apart from the campaign escrow
+        assert_eq!(token_client.balance(&donor), 0);
+        assert_eq!(token_client.balance(&contract_id), 100_000_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 98_000_000);
+        assert_eq!(client.get_fee_account(&token).collected, 2_000_000);
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().current_amount, 98_000_000);
 
-        if xlm_amount == 0 {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::InvalidAmount as u32));
-        }
+        // A donor without enough balance cannot donate
+        let broke_donor = Address::generate(&env);
+        assert!(client.try_donate(&campaign_id, &broke_donor, &10_000_000, &false, &false, &None, &None).is_err());
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 98_000_000);
+    }
 
-        // Convert XLM to pesos
-        let peso_amount = (xlm_amount * campaign.peso_exchange_rate) / 10000;
+    #[test]
+    fn test_milestone_release_requires_verified_docs() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-        // Get platform fee
-        let platform_fee_rate: u64 = env.storage().instance().get(&DataKey::PlatformFee).unwrap_or(200);
-        let platform_fee = (xlm_amount * platform_fee_rate) / 10000;
-        let net_xlm_amount = xlm_amount - platform_fee;
-        let net_peso_amount = (net_xlm_amount * campaign.peso_exchange_rate) / 10000;
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        client.add_medical_verifier(&verifier);
+        fund(&env, &token, &donor, 500_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
 
-        // Generate donation ID
-        let counter: u64 = env.storage().instance().get(&DataKey::DonationCounter).unwrap_or(0);
-        let new_counter = counter + 1;
-        env.storage().instance().set(&DataKey::DonationCounter, &new_counter);
+        let first = client.add_milestone(&campaign_id, &200_000, &Vec::from_array(&env, [MedicalDocType::TreatmentPlan]));
+        let second = client.add_milestone(
+            &campaign_id,
+            &200_000,
+            &Vec::from_array(&env, [MedicalDocType::TreatmentPlan, MedicalDocType::MedicalInvoice]),
+        );
+        assert_eq!((first, second), (0, 1));
+
+        // Milestones cannot add up to more than the goal, however large the amount
+        assert!(client
+            .try_add_milestone(&campaign_id, &200_000, &Vec::from_array(&env, [MedicalDocType::HospitalBill]))
+            .is_err());
+        assert_eq!(
+            client.try_add_milestone(&campaign_id, &u64::MAX, &Vec::from_array(&env, [MedicalDocType::HospitalBill])),
+            Err(Ok(SaviaError::InvalidAmount))
+        );
 
-        let mut hash_input = Bytes::new(&env);
-        hash_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
-        hash_input.append(&donor.clone().to_xdr(&env));
-        hash_input.append(&Bytes::from_slice(&env, &xlm_amount.to_be_bytes()));
-        hash_input.append(&Bytes::from_slice(&env, &current_time.to_be_bytes()));
-        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
-        
-        let donation_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+        // The plan is fixed once donations arrive
+        client.donate(&campaign_id, &donor, &500_000, &false, &false, &None, &None);
+        assert_eq!(
+            client.try_add_milestone(&campaign_id, &50_000, &Vec::from_array(&env, [MedicalDocType::HospitalBill])),
+            Err(Ok(SaviaError::MilestonePlanLocked))
+        );
 
-        // Create EtherFuse transaction
-        let etherfuse_tx_id = Self::create_etherfuse_transaction(
-            env.clone(),
-            campaign_id.clone(),
-            donor.clone(),
-            net_peso_amount,
-            net_xlm_amount,
-            campaign.peso_exchange_rate,
-            campaign.etherfuse_account.clone(),
-        )?;
+        // Nothing is released while the treatment plan is pending
+        let plan = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::TreatmentPlan,
+            &String::from_str(&env, "https://example.com/plan.pdf"),
+            &String::from_str(&env, "Plan de tratamiento"),
+        );
+        assert_eq!(
+            client.try_release_milestone(&campaign_id, &first),
+            Err(Ok(SaviaError::MilestoneDocsNotVerified))
+        );
 
-        // Create donation record
-        let donation = Donation {
-            id: donation_id.clone(),
-            campaign_id: campaign_id.clone(),
-            donor: donor.clone(),
-            amount: net_xlm_amount,
-            peso_amount: net_peso_amount,
-            timestamp: current_time,
-            nft_minted: mint_nft,
-            anonymous,
-            refunded: false,
-            etherfuse_tx_id: etherfuse_tx_id.clone(),
-        };
+        client.verify_medical_documentation(&plan, &verifier, &true);
+        assert_eq!(client.release_milestone(&campaign_id, &first), 200_000);
+        assert_eq!(token_client.balance(&beneficiary), 200_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 290_000);
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().released_amount, 200_000);
+        assert_eq!(
+            client.try_release_milestone(&campaign_id, &first),
+            Err(Ok(SaviaError::MilestoneAlreadyReleased))
+        );
+
+        // The plan used for the first milestone cannot unlock the second one
+        let invoice = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::MedicalInvoice,
+            &String::from_str(&env, "https://example.com/invoice.pdf"),
+            &String::from_str(&env, "Factura hospitalaria"),
+        );
+        client.verify_medical_documentation(&invoice, &verifier, &true);
+        assert!(client.try_release_milestone(&campaign_id, &second).is_err());
+
+        let second_plan = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::TreatmentPlan,
+            &String::from_str(&env, "https://example.com/plan-2.pdf"),
+            &String::from_str(&env, "Segunda fase"),
+        );
+        client.verify_medical_documentation(&second_plan, &verifier, &true);
+
+        // Locked campaigns cannot pay out, and new proof does not lift a pause
+        client.emergency_pause_campaign(&campaign_id);
+        client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::LabResults,
+            &String::from_str(&env, "https://example.com/labs.pdf"),
+            &String::from_str(&env, "Resultados"),
+        );
+        assert_eq!(
+            client.try_release_milestone(&campaign_id, &second),
+            Err(Ok(SaviaError::FundsLocked))
+        );
+        client.resume_campaign(&campaign_id);
+
+        client.release_milestone(&campaign_id, &second);
+        assert_eq!(token_client.balance(&beneficiary), 400_000);
+        assert_eq!(client.get_milestones(&campaign_id).get(1).unwrap().linked_docs.len(), 2);
+    }
 
-        // Update campaign progress
-        campaign.current_amount += net_xlm_amount;
-        env.storage().persistent().set(&DataKey::Campaign(campaign_id.clone()), &campaign);
+    #[test]
+    fn test_milestone_release_rejects_expired_docs_and_lapsed_kyc() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let (_, token) = setup(&env, &client);
 
-        // Store donation
-        env.storage().persistent().set(&DataKey::Donation(donation_id.clone()), &donation);
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        client.add_medical_verifier(&verifier);
+        fund(&env, &token, &donor, 500_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let milestone = client.add_milestone(&campaign_id, &100_000, &Vec::from_array(&env, [MedicalDocType::TreatmentPlan]));
+        client.donate(&campaign_id, &donor, &500_000, &false, &false, &None, &None);
 
-        // Update trust score
-        Self::update_donor_trust_score(env.clone(), donor.clone(), net_peso_amount)?;
+        let plan = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::TreatmentPlan,
+            &String::from_str(&env, "https://example.com/plan.pdf"),
+            &String::from_str(&env, "Plan de tratamiento"),
+        );
+        client.verify_medical_documentation(&plan, &verifier, &true);
 
-        // Handle dynamic NFT
-        if mint_nft {
-            Self::mint_or_update_dynamic_nft(env.clone(), donor, campaign_id, net_xlm_amount)?;
-        }
+        // A verified plan past its 90-day validity no longer backs a release
+        env.ledger().with_mut(|li| li.timestamp += 91 * 24 * 60 * 60);
+        assert_eq!(
+            client.try_release_milestone(&campaign_id, &milestone),
+            Err(Ok(SaviaError::MilestoneDocsNotVerified))
+        );
 
-        Ok(donation_id)
+        // Fresh documents do not help once the beneficiary's KYC has lapsed
+        env.ledger().with_mut(|li| li.timestamp += 300 * 24 * 60 * 60);
+        let fresh_plan = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::TreatmentPlan,
+            &String::from_str(&env, "https://example.com/plan-2.pdf"),
+            &String::from_str(&env, "Plan actualizado"),
+        );
+        client.verify_medical_documentation(&fresh_plan, &verifier, &true);
+        assert_eq!(
+            client.try_release_milestone(&campaign_id, &milestone),
+            Err(Ok(SaviaError::KYCExpired))
+        );
     }
 
-    /// Create EtherFuse transaction for peso conversion
-    fn create_etherfuse_transaction(
-        env: Env,
-        campaign_id: BytesN<32>,
-        donor: Address,
-        peso_amount: u64,
-        xlm_amount: u64,
-        exchange_rate: u64,
-        etherfuse_account: String,
-    ) -> Result<String, soroban_sdk::Error> {
-        // Generate transaction hash
-        let mut hash_input = Bytes::new(&env);
-        hash_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
-        hash_input.append(&donor.clone().to_xdr(&env));
-        hash_input.append(&Bytes::from_slice(&env, &peso_amount.to_be_bytes()));
-        hash_input.append(&Bytes::from_slice(&env, &env.ledger().timestamp().to_be_bytes()));
-        
-        let tx_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
-
-        let etherfuse_tx = EtherFuseTransaction {
-            id: tx_id.clone(),
-            campaign_id: campaign_id.clone(),
-            donor,
-            peso_amount,
-            xlm_amount,
-            exchange_rate,
-            etherfuse_tx_id: String::from_str(&env, "ETF_"),
-            status: TransactionStatus::Pending,
-            timestamp: env.ledger().timestamp(),
-        };
+    #[test]
+    fn test_refunds_return_escrow_to_donors() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-        env.storage().persistent().set(&DataKey::EtherFuseTransaction(tx_id), &etherfuse_tx);
-        
-        // Return simulated EtherFuse transaction ID
-        Ok(String::from_str(&env, "ETF_TX_123456"))
-    }
+        let beneficiary = Address::generate(&env);
+        let donor_a = Address::generate(&env);
+        let donor_b = Address::generate(&env);
+        fund(&env, &token, &donor_a, 100_000);
+        fund(&env, &token, &donor_b, 300_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
 
-    /// Mint or update dynamic NFT tree
-    fn mint_or_update_dynamic_nft(
-        env: Env,
-        donor: Address,
-        campaign_id: BytesN<32>,
-        peso_amount: u64,
-    ) -> Result<(), soroban_sdk::Error> {
-        // Create a key based on donor + campaign
-        let mut nft_key_input = Bytes::new(&env);
-        nft_key_input.append(&donor.clone().to_xdr(&env));
-        nft_key_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
-        let nft_key: BytesN<32> = env.crypto().sha256(&nft_key_input).into();
+        let donation_a = client.donate(&campaign_id, &donor_a, &100_000, &false, &false, &None, &None);
+        let donation_b = client.donate(&campaign_id, &donor_b, &300_000, &false, &false, &None, &None);
 
-        // Check if NFT already exists
-        if let Some(mut nft) = env.storage().persistent().get::<DataKey, DynamicNFT>(&DataKey::DynamicNFT(nft_key.clone())) {
-            // Update existing NFT
-            nft.total_donated += peso_amount;
-            nft.donation_count += 1;
-            nft.last_updated = env.ledger().timestamp();
-            
-            // Update growth stage based on total donated
-            nft.growth_stage = Self::calculate_growth_stage(nft.total_donated);
-            
-            // Check for achievements
-            if nft.donation_count == 10 {
-                nft.special_achievements.push_back(String::from_str(&env, "Consistent Supporter"));
-            }
-            if nft.total_donated >= 1_000_000_000 { // 100+ XLM
-                nft.special_achievements.push_back(String::from_str(&env, "Major Donor"));
-            }
+        // Refunds are only available once the campaign enters the refunding state
+        assert_eq!(
+            client.try_process_refund(&donation_a),
+            Err(Ok(SaviaError::RefundNotAvailable))
+        );
 
-            env.storage().persistent().set(&DataKey::DynamicNFT(nft_key), &nft);
-        } else {
-            // Create new NFT
-            let current_time = env.ledger().timestamp();
-            let growth_stage = Self::calculate_growth_stage(peso_amount);
-            
-            let nft = DynamicNFT {
-                id: nft_key.clone(),
-                owner: donor,
-                campaign_id,
-                tree_level: 1,
-                total_donated: peso_amount,
-                donation_count: 1,
-                created_at: current_time,
-                last_updated: current_time,
-                metadata_uri: String::from_str(&env, "https://drive.google.com/file/d/1RadoLAjnG00YPC3F2PNnB49PfuMorNZ8/view"),
-                growth_stage,
-                special_achievements: Vec::new(&env),
-            };
+        client.start_campaign_refunds(&campaign_id);
+        let campaign = client.get_campaign(&campaign_id).unwrap();
+        assert!(campaign.refunding);
+        assert!(campaign.funds_locked);
+
+        // Anyone can trigger the refund; the money always goes to the donor
+        assert_eq!(client.process_refund(&donation_a), 98_000);
+        assert_eq!(token_client.balance(&donor_a), 98_000);
+        assert!(client.get_donation(&donation_a).unwrap().refunded);
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().current_amount, 294_000);
+        assert_eq!(client.get_trust_score(&donor_a).unwrap().donation_count, 0);
+
+        let tx_id = client.get_donation(&donation_a).unwrap().etherfuse_tx_id.unwrap();
+        assert!(matches!(
+            client.get_etherfuse_transaction(&tx_id).unwrap().status,
+            TransactionStatus::Refunded
+        ));
+
+        // Claiming twice pays nothing
+        assert_eq!(client.process_refund(&donation_a), 0);
+        assert_eq!(token_client.balance(&donor_a), 98_000);
+
+        // Bare records put nothing in escrow and cannot claim from it
+        let attacker = Address::generate(&env);
+        let recorded = client.record_donation(&campaign_id, &attacker, &400_000);
+        assert_eq!(
+            client.try_process_refund(&recorded),
+            Err(Ok(SaviaError::RefundNotAvailable))
+        );
+        assert_eq!(token_client.balance(&attacker), 0);
 
-            env.storage().persistent().set(&DataKey::DynamicNFT(nft_key), &nft);
-        }
+        assert_eq!(client.process_refund(&donation_b), 294_000);
+        assert_eq!(token_client.balance(&donor_b), 294_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 0);
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().current_amount, 0);
 
-        Ok(())
+        // Paused-for-refund campaigns stay closed
+        assert!(client.try_donate(&campaign_id, &donor_a, &1_000, &false, &false, &None, &None).is_err());
     }
 
-    /// Calculate tree growth stage based on total XLM donated (in stroops)
-    fn calculate_growth_stage(total_stroops: u64) -> TreeGrowthStage {
-        match total_stroops {
-            0..=99_999_999 => TreeGrowthStage::PreSeed,       // < 10 XLM
-            100_000_000..=249_999_999 => TreeGrowthStage::Seed,     // 10-24 XLM
-            250_000_000..=499_999_999 => TreeGrowthStage::Sprout,   // 25-49 XLM
-            500_000_000..=999_999_999 => TreeGrowthStage::Bloom,    // 50-99 XLM
-            1_000_000_000..=1_999_999_999 => TreeGrowthStage::Blossom, // 100-199 XLM
-            2_000_000_000..=4_999_999_999 => TreeGrowthStage::Tree,    // 200-499 XLM
-            _ => TreeGrowthStage::SaviaTree,                           // 500+ XLM
-        }
-    }
+    #[test]
+    fn test_missed_proof_deadline_refunds_remaining_escrow_pro_rata() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let (_, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-    /// Check and process expired proof deadlines
-    pub fn check_proof_deadlines(env: Env, campaign_id: BytesN<32>) -> Result<(), soroban_sdk::Error> {
-        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+        let beneficiary = Address::generate(&env);
+        let donor_a = Address::generate(&env);
+        let donor_b = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        client.add_medical_verifier(&verifier);
+        fund(&env, &token, &donor_a, 100_000);
+        fund(&env, &token, &donor_b, 100_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let milestone = client.add_milestone(&campaign_id, &98_000, &Vec::from_array(&env, [MedicalDocType::TreatmentPlan]));
 
-        let current_time = env.ledger().timestamp();
+        let donation_a = client.donate(&campaign_id, &donor_a, &100_000, &false, &false, &None, &None);
+        let donation_b = client.donate(&campaign_id, &donor_b, &100_000, &false, &false, &None, &None);
 
-        // Check if 30 days have passed without proof submission
-        if campaign.last_proof_submitted > 0 && current_time > campaign.proof_deadline {
-            campaign.funds_locked = true;
-            
-            // Reduce trust score for late submission
-            if let Some(mut trust_score) = env.storage().persistent().get::<DataKey, TrustScore>(&DataKey::TrustScore(campaign.beneficiary.clone())) {
-                trust_score.late_submissions += 1;
-                trust_score.score = if trust_score.score > 20 { trust_score.score - 20 } else { 0 };
-                env.storage().persistent().set(&DataKey::TrustScore(campaign.beneficiary.clone()), &trust_score);
-            }
+        // Half of the escrow is paid out against a verified treatment plan
+        let plan = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::TreatmentPlan,
+            &String::from_str(&env, "https://example.com/plan.pdf"),
+            &String::from_str(&env, "Plan de tratamiento"),
+        );
+        client.verify_medical_documentation(&plan, &verifier, &true);
+        client.release_milestone(&campaign_id, &milestone);
+
+        // No further proof arrives within 30 days
+        env.ledger().with_mut(|li| li.timestamp += 31 * 24 * 60 * 60);
+        client.check_proof_deadlines(&campaign_id);
+        assert!(client.get_campaign(&campaign_id).unwrap().refunding);
+
+        // Each donor gets the same share of what is left
+        assert_eq!(client.process_refund(&donation_b), 49_000);
+        assert_eq!(client.process_refund(&donation_a), 49_000);
+        assert_eq!(token_client.balance(&donor_a), 49_000);
+        assert_eq!(token_client.balance(&donor_b), 49_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 0);
+
+        // Submitting late proof does not reopen a refunding campaign
+        client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::LabResults,
+            &String::from_str(&env, "https://example.com/labs.pdf"),
+            &String::from_str(&env, "Resultados"),
+        );
+        assert!(client.get_campaign(&campaign_id).unwrap().funds_locked);
+    }
 
-            // TODO: Implement refund logic for donations
-            Self::initiate_refund_process(env.clone(), campaign_id.clone())?;
-        }
+    #[test]
+    fn test_refund_claim_window_expires() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
 
-        env.storage().persistent().set(&DataKey::Campaign(campaign_id), &campaign);
-        Ok(())
-    }
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 100_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let donation_id = client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None);
+        let treasury = Address::generate(&env);
+
+        client.start_campaign_refunds(&campaign_id);
+        assert_eq!(
+            client.try_sweep_unclaimed_refunds(&campaign_id, &treasury),
+            Err(Ok(SaviaError::RefundWindowOpen))
+        );
+        env.ledger().with_mut(|li| li.timestamp += 31 * 24 * 60 * 60);
+        assert_eq!(
+            client.try_process_refund(&donation_id),
+            Err(Ok(SaviaError::RefundPeriodExpired))
+        );
 
-    /// Initiate refund process for donors
-    fn initiate_refund_process(env: Env, campaign_id: BytesN<32>) -> Result<(), soroban_sdk::Error> {
-        // In a real implementation, this would iterate through all donations
-        // and initiate refunds through EtherFuse
-        
-        // For now, we'll just mark the campaign as requiring refunds
-        // The actual refund logic would be implemented separately
-        Ok(())
+        // Unclaimed escrow can be recovered once the window has closed
+        assert_eq!(client.sweep_unclaimed_refunds(&campaign_id, &treasury), 98_000);
+        assert_eq!(TokenClient::new(&env, &token).balance(&treasury), 98_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 0);
     }
 
-    /// Update peso exchange rate
-    pub fn update_peso_exchange_rate(env: Env, new_rate: u64) -> Result<(), soroban_sdk::Error> {
-        env.storage().instance().set(&DataKey::PesoExchangeRate, &new_rate);
-        Ok(())
-    }
+    #[test]
+    fn test_reversed_donations_leave_donor_totals() {
+        let env = Env::default();
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (admin, token) = setup(&env, &client);
 
-    /// Add KYC verifier
-    pub fn add_kyc_verifier(env: Env, verifier: Address) -> Result<(), soroban_sdk::Error> {
-        let mut verifiers: Vec<Address> = env.storage().instance().get(&DataKey::KYCVerifiers).unwrap_or(Vec::new(&env));
-        if !verifiers.contains(&verifier) {
-            verifiers.push_back(verifier);
-            env.storage().instance().set(&DataKey::KYCVerifiers, &verifiers);
-        }
-        Ok(())
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let operator = Address::generate(&env);
+        client.add_etherfuse_operator(&operator);
+        fund(&env, &token, &donor, 300_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        client.set_compliance_config(&admin, &ComplianceConfig {
+            unit: LimitUnit::Pesos,
+            uma_value: 0,
+            verified_donor_threshold: 0,
+            period: 24 * 60 * 60,
+        });
+        client.set_aml_config(&admin, &AmlConfig { threshold: 3_000_000, window: 7 * 24 * 60 * 60 });
+
+        // A failed conversion no longer counts towards the NFT, limits or AML
+        let failed = client.donate(&campaign_id, &donor, &100_000, &false, &true, &None, &None);
+        assert_eq!(client.get_donor_period_total(&donor).total, 1_800_000);
+        let tx_id = client.get_donation(&failed).unwrap().etherfuse_tx_id.unwrap();
+        client.fail_etherfuse_transaction(&operator, &tx_id, &String::from_str(&env, "EF-2024-000300"));
+        let nft = client.get_donor_nft(&donor, &campaign_id).unwrap();
+        assert_eq!((nft.total_donated, nft.donation_count), (0, 0));
+        assert_eq!(client.get_donor_period_total(&donor).total, 0);
+
+        let first = client.donate(&campaign_id, &donor, &100_000, &false, &true, &None, &None);
+        assert_eq!(client.get_aml_alert_count(), 0);
+        let second = client.donate(&campaign_id, &donor, &100_000, &false, &true, &None, &None);
+        assert_eq!(client.get_aml_alert_count(), 1);
+
+        // Refunds reverse the same totals
+        client.start_campaign_refunds(&campaign_id);
+        client.process_refund(&first);
+        client.process_refund(&second);
+        let nft = client.get_donor_nft(&donor, &campaign_id).unwrap();
+        assert_eq!((nft.total_donated, nft.donation_count, nft.growth_stage), (0, 0, TreeGrowthStage::PreSeed));
+        assert_eq!(client.get_donor_period_total(&donor).total, 0);
+        assert_eq!(client.get_trust_score(&donor).unwrap().donation_count, 0);
     }
 
-    /// Add medical verifier
-    pub fn add_medical_verifier(env: Env, verifier: Address) -> Result<(), soroban_sdk::Error> {
-        let mut verifiers: Vec<Address> = env.storage().instance().get(&DataKey::MedicalVerifiers).unwrap_or(Vec::new(&env));
-        if !verifiers.contains(&verifier) {
-            verifiers.push_back(verifier);
-            env.storage().instance().set(&DataKey::MedicalVerifiers, &verifiers);
-        }
-        Ok(())
-    }
+    #[test]
+    fn test_fee_treasury_withdrawal() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (admin, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-    /// Get campaign details
-    pub fn get_campaign(env: Env, campaign_id: BytesN<32>) -> Option<Campaign> {
-        env.storage().persistent().get(&DataKey::Campaign(campaign_id))
-    }
+        let treasury = Address::generate(&env);
+        let outsider = Address::generate(&env);
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 100_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        client.set_treasury(&treasury);
 
-    /// Get donation details
-    pub fn get_donation(env: Env, donation_id: BytesN<32>) -> Option<Donation> {
-        env.storage().persistent().get(&DataKey::Donation(donation_id))
-    }
+        client.donate(&campaign_id, &donor, &100_000_000, &false, &false, &None, &None);
+        assert_eq!(
+            client.get_fee_account(&token),
+            FeeAccount { collected: 2_000_000, withdrawn: 0 }
+        );
 
-    /// Get KYC record
-    pub fn get_kyc_record(env: Env, entity: Address) -> Option<KYCRecord> {
-        env.storage().persistent().get(&DataKey::KYCRecord(entity))
-    }
+        // Only the admin or the treasury may pull fees
+        assert_eq!(
+            client.try_withdraw_fees(&outsider, &token, &outsider, &1_000_000),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
+        assert_eq!(
+            client.try_withdraw_fees(&treasury, &token, &treasury, &3_000_000),
+            Err(Ok(SaviaError::InsufficientFunds))
+        );
 
-    /// Get medical documentation
-    pub fn get_medical_documentation(env: Env, doc_hash: BytesN<32>) -> Option<MedicalDocumentation> {
-        env.storage().persistent().get(&DataKey::MedicalDoc(doc_hash))
-    }
+        client.withdraw_fees(&treasury, &token, &treasury, &1_500_000);
+        client.withdraw_fees(&admin, &token, &treasury, &500_000);
+        assert_eq!(token_client.balance(&treasury), 2_000_000);
+        assert_eq!(
+            client.get_fee_account(&token),
+            FeeAccount { collected: 2_000_000, withdrawn: 2_000_000 }
+        );
 
-    /// Get dynamic NFT
-    pub fn get_dynamic_nft(env: Env, nft_id: BytesN<32>) -> Option<DynamicNFT> {
-        env.storage().persistent().get(&DataKey::DynamicNFT(nft_id))
+        // Escrowed donations are never touched by fee withdrawals
+        assert_eq!(token_client.balance(&contract_id), 98_000_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 98_000_000);
     }
 
-    /// Get donor's NFT for a specific campaign
-    pub fn get_donor_nft(env: Env, donor: Address, campaign_id: BytesN<32>) -> Option<DynamicNFT> {
-        let mut nft_key_input = Bytes::new(&env);
-        nft_key_input.append(&donor.to_xdr(&env));
-        nft_key_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
-        let nft_key: BytesN<32> = env.crypto().sha256(&nft_key_input).into();
-        
-        env.storage().persistent().get(&DataKey::DynamicNFT(nft_key))
-    }
+    #[test]
+    fn test_set_platform_fee_bounds() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
 
-    /// Get trust score
-    pub fn get_trust_score(env: Env, entity: Address) -> Option<TrustScore> {
-        env.storage().persistent().get(&DataKey::TrustScore(entity))
+        assert_eq!(
+            client.try_set_platform_fee(&1001),
+            Err(Ok(SaviaError::InvalidFee))
+        );
+        client.set_platform_fee(&500);
+        assert_eq!(client.get_platform_fee(), 500);
+
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 1_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        client.donate(&campaign_id, &donor, &1_000_000, &false, &false, &None, &None);
+        assert_eq!(client.get_fee_account(&token).collected, 50_000);
+
+        env.set_auths(&[]);
+        assert!(client.try_set_platform_fee(&100).is_err());
+        assert!(client.try_set_treasury(&donor).is_err());
     }
 
-    /// Get EtherFuse transaction
-    pub fn get_etherfuse_transaction(env: Env, tx_id: BytesN<32>) -> Option<EtherFuseTransaction> {
-        env.storage().persistent().get(&DataKey::EtherFuseTransaction(tx_id))
+    #[test]
+    fn test_donation_indexes_paginate_and_hide_anonymous_donors() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let other_donor = Address::generate(&env);
+        fund(&env, &token, &donor, 1_000_000);
+        fund(&env, &token, &other_donor, 1_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let other_campaign = create_test_campaign(&env, &client, &beneficiary);
+
+        let first = client.donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None);
+        let hidden = client.donate(&campaign_id, &donor, &20_000, &true, &false, &None, &None);
+        let third = client.donate(&campaign_id, &other_donor, &30_000, &false, &false, &None, &None);
+        let elsewhere = client.donate(&other_campaign, &donor, &40_000, &false, &false, &None, &None);
+
+        // Bare records carry no tokens and never reach the public listings
+        client.record_donation(&campaign_id, &donor, &50_000);
+
+        assert_eq!(client.get_campaign_donation_count(&campaign_id), 3);
+        let page = client.list_campaign_donations(&campaign_id, &0, &2);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page.get(0).unwrap().id, first);
+        assert_eq!(page.get(0).unwrap().donor, Some(donor.clone()));
+        assert_eq!(page.get(1).unwrap().id, hidden);
+        assert_eq!(page.get(1).unwrap().donor, None);
+
+        let page = client.list_campaign_donations(&campaign_id, &2, &2);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page.get(0).unwrap().id, third);
+        assert_eq!(client.list_campaign_donations(&campaign_id, &5, &2).len(), 0);
+
+        // The anonymous donation never shows up in the donor's public history
+        assert_eq!(client.get_donor_donation_count(&donor), 2);
+        let history = client.list_donor_donations(&donor, &0, &10);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.get(0).unwrap().id, first);
+        assert_eq!(history.get(1).unwrap().id, elsewhere);
     }
 
-    /// Initialize enhanced trust score
-    pub fn initialize_trust_score(env: Env, entity: Address) -> Result<(), soroban_sdk::Error> {
-        if env.storage().persistent().has(&DataKey::TrustScore(entity.clone())) {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::ScoreExists as u32));
-        }
+    #[test]
+    fn test_list_campaigns_with_filters_and_cursor() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
 
-        let trust_score = TrustScore {
-            entity: entity.clone(),
-            score: 50, // Start with neutral score
-            verification_level: 0,
-            donation_count: 0,
-            total_donated: 0,
-            campaigns_created: 0,
-            medical_docs_submitted: 0,
-            docs_verified_on_time: 0,
-            late_submissions: 0,
-            fraud_reports: 0,
-            last_updated: env.ledger().timestamp(),
+        let beneficiary = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        client.add_kyc_verifier(&verifier);
+        register_beneficiary(&env, &client, &beneficiary);
+
+        let new_campaign = |category: &str, location: &str, duration_days: u64| {
+            client.create_campaign(
+                &beneficiary,
+                &String::from_str(&env, "Tratamiento"),
+                &String::from_str(&env, "Ayuda médica"),
+                &String::from_str(&env, "Cirugía"),
+                &500000,
+                &duration_days,
+                &String::from_str(&env, category),
+                &String::from_str(&env, location),
+                &String::from_str(&env, "ETF_ACCOUNT_123"),
+            )
         };
+        let gdl_health = new_campaign("Salud", "Guadalajara", 60);
+        let cdmx_health = new_campaign("Salud", "Mexico City", 1);
+        let gdl_surgery = new_campaign("Cirugía", "Guadalajara", 60);
+        let cdmx_surgery = new_campaign("Cirugía", "Mexico City", 60);
+        client.verify_campaign(&gdl_surgery, &80, &verifier);
+        assert_eq!(client.get_campaign_count(), 4);
+
+        let any = CampaignFilter { category: None, location: None, verified: None, active: None };
+
+        // Cursor pagination walks the whole registry in creation order
+        let page = client.list_campaigns(&any, &0, &3);
+        assert_eq!(page.campaigns.len(), 3);
+        assert_eq!(page.campaigns.get(0).unwrap().id, gdl_health);
+        assert_eq!(page.next_cursor, Some(4));
+        let page = client.list_campaigns(&any, &page.next_cursor.unwrap(), &3);
+        assert_eq!(page.campaigns.len(), 1);
+        assert_eq!(page.campaigns.get(0).unwrap().id, cdmx_surgery);
+        assert_eq!(page.next_cursor, None);
+
+        let page = client.list_campaigns(
+            &CampaignFilter { category: Some(String::from_str(&env, "Salud")), ..any.clone() },
+            &0,
+            &10,
+        );
+        assert_eq!(page.campaigns.len(), 2);
 
-        env.storage().persistent().set(&DataKey::TrustScore(entity), &trust_score);
-        Ok(())
+        let page = client.list_campaigns(
+            &CampaignFilter { location: Some(String::from_str(&env, "Guadalajara")), verified: Some(true), ..any.clone() },
+            &0,
+            &10,
+        );
+        assert_eq!(page.campaigns.len(), 1);
+        assert_eq!(page.campaigns.get(0).unwrap().id, gdl_surgery);
+
+        // The one-day campaign ends first
+        env.ledger().with_mut(|li| li.timestamp += 2 * 24 * 60 * 60);
+        let ended = client.list_campaigns(&CampaignFilter { active: Some(false), ..any.clone() }, &0, &10);
+        assert_eq!(ended.campaigns.len(), 1);
+        assert_eq!(ended.campaigns.get(0).unwrap().id, cdmx_health);
+        let active = client.list_campaigns(&CampaignFilter { active: Some(true), ..any }, &0, &10);
+        assert_eq!(active.campaigns.len(), 3);
     }
 
-    /// Enhanced trust score update
-    fn update_donor_trust_score(env: Env, donor: Address, peso_amount: u64) -> Result<(), soroban_sdk::Error> {
-        let mut trust_score: TrustScore = env.storage().persistent().get(&DataKey::TrustScore(donor.clone()))
-            .unwrap_or(TrustScore {
-                entity: donor.clone(),
-                score: 50,
-                verification_level: 0,
-                donation_count: 0,
-                total_donated: 0,
-                campaigns_created: 0,
-                medical_docs_submitted: 0,
-                docs_verified_on_time: 0,
-                late_submissions: 0,
-                fraud_reports: 0,
-                last_updated: env.ledger().timestamp(),
-            });
+    #[test]
+    fn test_campaign_lifecycle_events() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (admin, token) = setup(&env, &client);
 
-        trust_score.donation_count += 1;
-        trust_score.total_donated += peso_amount;
-        trust_score.last_updated = env.ledger().timestamp();
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        client.add_medical_verifier(&verifier);
+        fund(&env, &token, &donor, 200_000_000);
 
-        // Enhanced scoring algorithm
-        let donation_factor = if trust_score.donation_count > 100 { 100 } else { trust_score.donation_count };
-        let amount_factor = if trust_score.total_donated > 100000 { 100000 } else { trust_score.total_donated };
-        let consistency_factor = if trust_score.donation_count > 1 { 120u64 } else { 100u64 };
-        let verification_bonus = match trust_score.verification_level {
-            0 => 0,
-            1 => 10,
-            2 => 20,
-            _ => 30,
-        };
+        register_beneficiary(&env, &client, &beneficiary);
+        let kyc_event: KYCVerifiedEvent = find_event(&env, &contract_id, "kyc", "verified").unwrap();
+        assert_eq!(kyc_event.verification_level, KYCLevel::BasicVerified);
+
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let events = savia_events(&env, &contract_id);
+        assert_eq!(events.len(), 1);
+        let (topics, data) = events.get(0).unwrap();
+        assert_eq!(topics, (symbol_short!("campaign"), symbol_short!("created"), campaign_id.clone()).into_val(&env));
+        let created = CampaignCreatedEvent::try_from_val(&env, &data).unwrap();
+        assert_eq!(created.beneficiary, beneficiary);
+        assert_eq!(created.goal_amount, 500000);
+
+        // Anonymous donations do not reveal the donor in the event either
+        let donation_id = client.donate(&campaign_id, &donor, &50_000_000, &true, &true, &None, &None);
+        let donated: DonationEvent = find_event(&env, &contract_id, "donation", "received").unwrap();
+        assert_eq!(donated.donation_id, donation_id);
+        assert_eq!(donated.donor, None);
+        assert_eq!(donated.amount, 49_000_000);
+        assert_eq!(donated.platform_fee, 1_000_000);
+        let minted: NFTMintedEvent = find_event(&env, &contract_id, "nft", "minted").unwrap();
+        assert_eq!(minted.growth_stage, TreeGrowthStage::PreSeed);
+
+        client.donate(&campaign_id, &donor, &150_000_000, &false, &true, &None, &None);
+        let grown: NFTGrowthEvent = find_event(&env, &contract_id, "nft", "grown").unwrap();
+        assert_eq!(grown.previous_stage, TreeGrowthStage::PreSeed);
+        assert_eq!(grown.growth_stage, TreeGrowthStage::Seed);
 
-        // Calculate new score with peso amounts
-        let base_score = 50u64;
-        let donation_component = (25u64 * donation_factor as u64) / 100u64;
-        let amount_component = (20u64 * amount_factor) / 100000u64;
-        let consistency_component = (consistency_factor * 5u64) / 100u64;
-        
-        let new_score = base_score + donation_component + amount_component + consistency_component + verification_bonus as u64;
-        
-        // Apply penalties
-        let penalty = (trust_score.late_submissions * 10) + (trust_score.fraud_reports * 30);
-        let final_score = if new_score > penalty as u64 { new_score - penalty as u64 } else { 0 };
-        
-        trust_score.score = if final_score > 100 { 100 } else { final_score as u32 };
+        let doc_hash = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::MedicalDiagnosis,
+            &String::from_str(&env, "https://example.com/medical-report.pdf"),
+            &String::from_str(&env, "Diagnóstico médico oficial"),
+        );
+        let submitted: DocumentSubmittedEvent = find_event(&env, &contract_id, "medical", "submitted").unwrap();
+        assert_eq!(submitted.document_hash, doc_hash);
 
-        env.storage().persistent().set(&DataKey::TrustScore(donor), &trust_score);
-        Ok(())
+        client.verify_medical_documentation(&doc_hash, &verifier, &true);
+        let verified: DocumentVerifiedEvent = find_event(&env, &contract_id, "medical", "verified").unwrap();
+        assert_eq!(verified.verifier, verifier);
+        assert!(verified.approved);
+
+        client.emergency_pause_campaign(&campaign_id);
+        assert_eq!(find_event::<Address>(&env, &contract_id, "campaign", "paused"), Some(admin.clone()));
+        client.resume_campaign(&campaign_id);
+        assert_eq!(find_event::<Address>(&env, &contract_id, "campaign", "resumed"), Some(admin));
+
+        client.start_campaign_refunds(&campaign_id);
+        let started: RefundPool = find_event(&env, &contract_id, "refund", "started").unwrap();
+        assert_eq!(started.raised_at_start, 196_000_000);
+
+        client.process_refund(&donation_id);
+        let refunded: RefundEvent = find_event(&env, &contract_id, "refund", "paid").unwrap();
+        assert_eq!(refunded.donor, donor);
+        assert_eq!(refunded.amount, 49_000_000);
     }
 
-    /// Report fraud
-    pub fn report_fraud(env: Env, reported_entity: Address, reporter: Address) -> Result<(), soroban_sdk::Error> {
-        // Verify reporter has some trust score
-        let reporter_trust: TrustScore = env.storage().persistent().get(&DataKey::TrustScore(reporter))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::NotAuthorized as u32))?;
+    #[test]
+    fn test_lock_and_fraud_events() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        setup(&env, &client);
 
-        if reporter_trust.score < 30 {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::NotAuthorized as u32));
-        }
+        let beneficiary = Address::generate(&env);
+        let reporter = Address::generate(&env);
+        client.initialize_trust_score(&beneficiary);
+        client.initialize_trust_score(&reporter);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::MedicalDiagnosis,
+            &String::from_str(&env, "https://example.com/medical-report.pdf"),
+            &String::from_str(&env, "Diagnóstico médico oficial"),
+        );
 
-        // Update reported entity's trust score
-        if let Some(mut trust_score) = env.storage().persistent().get::<DataKey, TrustScore>(&DataKey::TrustScore(reported_entity.clone())) {
-            trust_score.fraud_reports += 1;
-            trust_score.score = if trust_score.score > 30 { trust_score.score - 30 } else { 0 };
-            env.storage().persistent().set(&DataKey::TrustScore(reported_entity), &trust_score);
-        }
+        env.ledger().with_mut(|li| li.timestamp += 31 * 24 * 60 * 60);
+        client.check_proof_deadlines(&campaign_id);
+        let locked: CampaignLockedEvent = find_event(&env, &contract_id, "campaign", "locked").unwrap();
+        assert_eq!(locked.locked_at, env.ledger().timestamp());
+        assert!(find_event::<RefundPool>(&env, &contract_id, "refund", "started").is_some());
 
-        Ok(())
+        // Checking again does not lock twice
+        client.check_proof_deadlines(&campaign_id);
+        assert_eq!(savia_events(&env, &contract_id).len(), 0);
+
+        client.report_fraud(&beneficiary, &reporter);
+        let reported: FraudReportedEvent = find_event(&env, &contract_id, "fraud", "reported").unwrap();
+        assert_eq!(reported.reporter, reporter);
+        assert_eq!(reported.fraud_reports, 1);
     }
 
-    /// Process refund for donation
-    pub fn process_refund(env: Env, donation_id: BytesN<32>) -> Result<(), soroban_sdk::Error> {
-        let mut donation: Donation = env.storage().persistent().get(&DataKey::Donation(donation_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+    #[test]
+    fn test_entrypoints_return_precise_errors() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
 
-        if donation.refunded {
-            return Ok(()); // Already refunded
-        }
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        fund(&env, &token, &donor, 1_000_000);
+        let missing_id = BytesN::from_array(&env, &[7u8; 32]);
+
+        let create = |goal: u64, days: u64| {
+            client.try_create_campaign(
+                &beneficiary,
+                &String::from_str(&env, "Tratamiento"),
+                &String::from_str(&env, "Ayuda médica"),
+                &String::from_str(&env, "Cirugía"),
+                &goal,
+                &days,
+                &String::from_str(&env, "Salud"),
+                &String::from_str(&env, "Mexico City"),
+                &String::from_str(&env, "ETF_ACCOUNT_123"),
+            )
+        };
+        assert_eq!(create(500000, 60), Err(Ok(SaviaError::KYCNotVerified)));
+        register_beneficiary(&env, &client, &beneficiary);
+        assert_eq!(create(0, 60), Err(Ok(SaviaError::InvalidGoal)));
+        assert_eq!(create(500000, 0), Err(Ok(SaviaError::InvalidDuration)));
+        assert_eq!(create(500000, 366), Err(Ok(SaviaError::InvalidDuration)));
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+
+        assert_eq!(
+            client.try_donate(&missing_id, &donor, &1_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::CampaignNotFound))
+        );
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &0, &false, &false, &None, &None),
+            Err(Ok(SaviaError::InvalidAmount))
+        );
+        assert_eq!(client.try_process_refund(&missing_id), Err(Ok(SaviaError::DonationNotFound)));
+        assert_eq!(
+            client.try_verify_medical_documentation(&missing_id, &stranger, &true),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
+        client.add_medical_verifier(&stranger);
+        assert_eq!(
+            client.try_verify_medical_documentation(&missing_id, &stranger, &true),
+            Err(Ok(SaviaError::DocumentNotFound))
+        );
+        assert_eq!(client.try_release_milestone(&campaign_id, &0), Err(Ok(SaviaError::MilestoneNotFound)));
 
-        let campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(donation.campaign_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+        assert_eq!(client.try_report_fraud(&beneficiary, &stranger), Err(Ok(SaviaError::TrustScoreNotFound)));
+        client.initialize_trust_score(&stranger);
+        assert_eq!(client.try_initialize_trust_score(&stranger), Err(Ok(SaviaError::ScoreExists)));
 
-        let current_time = env.ledger().timestamp();
-        let refund_deadline = campaign.proof_deadline + (7 * 24 * 60 * 60); // 7 days after proof deadline
+        // Campaigns stop taking donations once they end, and lapsed KYC blocks new ones
+        env.ledger().with_mut(|li| li.timestamp += 366 * 24 * 60 * 60);
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &1_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::CampaignEnded))
+        );
+        assert_eq!(create(500000, 60), Err(Ok(SaviaError::KYCExpired)));
+    }
 
-        if current_time > refund_deadline {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::RefundPeriodExpired as u32));
-        }
+    #[test]
+    fn test_low_trust_reporters_and_uninitialized_contract_errors() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
 
-        // Mark donation as refunded
-        donation.refunded = true;
-        env.storage().persistent().set(&DataKey::Donation(donation_id), &donation);
+        assert_eq!(client.try_update_peso_exchange_rate(&190000), Err(Ok(SaviaError::NotInitialized)));
+        let token = Address::generate(&env);
+        assert_eq!(
+            client.try_initialize(&token, &1001, &String::from_str(&env, "etherfuse_config"), &180000),
+            Err(Ok(SaviaError::InvalidFee))
+        );
 
-        // Update EtherFuse transaction status
-        // In real implementation, this would trigger actual refund through EtherFuse
-        
-        Ok(())
+        let (admin, _) = setup(&env, &client);
+        let accuser = Address::generate(&env);
+        let accused = Address::generate(&env);
+        client.initialize_trust_score(&accuser);
+        client.initialize_trust_score(&accused);
+        assert_eq!(env.auths()[0].0, admin);
+
+        // Only the admin issues trust scores and mints NFTs directly
+        env.set_auths(&[]);
+        let stranger = Address::generate(&env);
+        assert!(client.try_initialize_trust_score(&stranger).is_err());
+        assert!(client.try_mint(&stranger, &String::from_str(&env, "{}"), &6).is_err());
+        env.mock_all_auths();
+
+        // Two reports drop the accused below the score needed to report others
+        client.report_fraud(&accused, &accuser);
+        client.report_fraud(&accused, &accuser);
+        assert_eq!(client.try_report_fraud(&accuser, &accused), Err(Ok(SaviaError::InsufficientTrustScore)));
     }
 
-    /// Get campaign statistics
-    pub fn get_campaign_stats(env: Env, campaign_id: BytesN<32>) -> Result<Map<String, u64>, soroban_sdk::Error> {
-        let campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+    #[test]
+    fn test_rate_oracle_aggregates_median_and_rejects_outliers() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
 
-        let mut stats = Map::new(&env);
-        stats.set(String::from_str(&env, "goal_amount"), campaign.goal_amount);
-        stats.set(String::from_str(&env, "current_amount"), campaign.current_amount);
-        stats.set(String::from_str(&env, "peso_goal"), (campaign.goal_amount * campaign.peso_exchange_rate) / 10000);
-        stats.set(String::from_str(&env, "peso_raised"), (campaign.current_amount * campaign.peso_exchange_rate) / 10000);
-        stats.set(String::from_str(&env, "days_remaining"), 
-            if env.ledger().timestamp() < campaign.end_time { 
-                (campaign.end_time - env.ledger().timestamp()) / (24 * 60 * 60) 
-            } else { 0 }
+        let reporters = [Address::generate(&env), Address::generate(&env), Address::generate(&env)];
+        for reporter in reporters.iter() {
+            client.add_rate_reporter(reporter);
+        }
+        client.set_oracle_config(&OracleConfig {
+            min_reports: 3,
+            max_deviation_bps: 500,
+            staleness_window: 60 * 60,
+        });
+
+        let outsider = Address::generate(&env);
+        assert_eq!(client.try_submit_peso_rate(&outsider, &181000), Err(Ok(SaviaError::NotAuthorized)));
+        assert_eq!(client.try_submit_peso_rate(&reporters[0], &0), Err(Ok(SaviaError::InvalidExchangeRate)));
+
+        // More than 5% away from the current 18.0 MXN/XLM
+        assert_eq!(
+            client.try_submit_peso_rate(&reporters[0], &190000),
+            Err(Ok(SaviaError::RateDeviationTooLarge))
         );
-        stats.set(String::from_str(&env, "trust_score"), campaign.trust_score as u64);
-        stats.set(String::from_str(&env, "kyc_verified"), if campaign.kyc_verified { 1 } else { 0 });
-        stats.set(String::from_str(&env, "medical_verified"), if campaign.medical_docs_verified { 1 } else { 0 });
-        stats.set(String::from_str(&env, "funds_locked"), if campaign.funds_locked { 1 } else { 0 });
 
-        Ok(stats)
+        // The rate only moves once enough reporters agree
+        assert_eq!(client.submit_peso_rate(&reporters[0], &185000), 180000);
+        assert_eq!(client.submit_peso_rate(&reporters[1], &181000), 180000);
+        assert_eq!(client.submit_peso_rate(&reporters[2], &188000), 185000);
+        let updated: RateUpdatedEvent = find_event(&env, &contract_id, "rate", "updated").unwrap();
+        assert_eq!(updated.rate, 185000);
+        assert_eq!(updated.report_count, 3);
+        assert_eq!(client.get_peso_exchange_rate(), 185000);
+
+        // Reports older than the staleness window drop out of the median
+        env.ledger().with_mut(|li| li.timestamp += 2 * 60 * 60);
+        assert_eq!(client.submit_peso_rate(&reporters[0], &186000), 185000);
+        client.remove_rate_reporter(&reporters[2]);
+        assert_eq!(client.get_rate_reports().len(), 2);
+        assert_eq!(client.get_rate_reporters().len(), 2);
     }
 
-    /// Get donor dashboard data
-    pub fn get_donor_dashboard(env: Env, donor: Address) -> Result<Map<String, u64>, soroban_sdk::Error> {
-        let mut dashboard = Map::new(&env);
+    #[test]
+    fn test_stale_exchange_rate_blocks_donations() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
 
-        // Get trust score
-        if let Some(trust_score) = env.storage().persistent().get::<DataKey, TrustScore>(&DataKey::TrustScore(donor.clone())) {
-            dashboard.set(String::from_str(&env, "trust_score"), trust_score.score as u64);
-            dashboard.set(String::from_str(&env, "total_donated"), trust_score.total_donated);
-            dashboard.set(String::from_str(&env, "donation_count"), trust_score.donation_count as u64);
-        }
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let reporter = Address::generate(&env);
+        client.add_rate_reporter(&reporter);
+        fund(&env, &token, &donor, 1_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
 
-        // Get KYC status
-        if let Some(kyc_record) = env.storage().persistent().get::<DataKey, KYCRecord>(&DataKey::KYCRecord(donor.clone())) {
-            let kyc_level = match kyc_record.verification_level {
-                KYCLevel::Unverified => 0,
-                KYCLevel::BasicVerified => 1,
-                KYCLevel::MedicalVerified => 2,
-                KYCLevel::FullyVerified => 3,
-            };
-            dashboard.set(String::from_str(&env, "kyc_level"), kyc_level);
-            dashboard.set(String::from_str(&env, "kyc_expires"), kyc_record.expires_at);
-        }
+        env.ledger().with_mut(|li| li.timestamp += 25 * 60 * 60);
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::StaleExchangeRate))
+        );
 
-        Ok(dashboard)
+        client.submit_peso_rate(&reporter, &182000);
+        assert_eq!(client.get_peso_rate_updated_at(), env.ledger().timestamp());
+        client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None);
+
+        // The admin can still push a rate directly, e.g. after a large market move
+        env.set_auths(&[]);
+        assert!(client.try_update_peso_exchange_rate(&250000).is_err());
+        env.mock_all_auths();
+        client.update_peso_exchange_rate(&250000);
+        assert_eq!(client.get_peso_exchange_rate(), 250000);
     }
 
-    /// Verify campaign (admin function)
-    pub fn verify_campaign(
-        env: Env,
-        campaign_id: BytesN<32>,
-        trust_score: u32,
-        verifier: Address,
-    ) -> Result<(), soroban_sdk::Error> {
-        // Check if verifier is authorized
-        let verifiers: Vec<Address> = env.storage().instance().get(&DataKey::KYCVerifiers).unwrap_or(Vec::new(&env));
-        if !verifiers.contains(&verifier) {
-            return Err(soroban_sdk::Error::from_contract_error(SaviaError::NotAuthorized as u32));
-        }
+    #[test]
+    fn test_price_feed_rate_with_fallback() {
+        let env = Env::default();
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+
+        let feed_id = env.register(MockPriceFeed, ());
+        let feed = MockPriceFeedClient::new(&env, &feed_id);
+        let xlm = Asset::Stellar(token.clone());
+        let mxn = Asset::Other(Symbol::new(&env, "MXN"));
+        let now = env.ledger().timestamp();
+
+        // 0.12 USD per XLM and 0.006 USD per MXN at 14 decimals -> 20 MXN per XLM
+        feed.set_price(&xlm, &12_000_000_000_000, &now);
+        client.set_price_feed(&PriceFeedConfig {
+            feed: feed_id.clone(),
+            xlm_asset: xlm.clone(),
+            mxn_asset: mxn.clone(),
+            max_age: 15 * 60,
+        });
+
+        // No MXN price yet: fall back to the stored rate
+        assert_eq!(
+            client.get_peso_rate_quote(),
+            PesoRateQuote { rate: 180000, updated_at: now, from_price_feed: false }
+        );
 
-        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+        feed.set_price(&mxn, &600_000_000_000, &now);
+        assert_eq!(
+            client.get_peso_rate_quote(),
+            PesoRateQuote { rate: 200000, updated_at: now, from_price_feed: true }
+        );
 
-        campaign.verified = true;
-        campaign.trust_score = trust_score;
+        // Campaigns pick up the feed rate
+        let beneficiary = Address::generate(&env);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().peso_exchange_rate, 200000);
 
-        env.storage().persistent().set(&DataKey::Campaign(campaign_id), &campaign);
-        Ok(())
+        // A stale feed price is ignored
+        env.ledger().with_mut(|li| li.timestamp += 20 * 60);
+        assert_eq!(client.get_peso_exchange_rate(), 180000);
+
+        client.clear_price_feed();
+        assert_eq!(client.get_price_feed(), None);
     }
 
-    /// Get current peso exchange rate
-    pub fn get_peso_exchange_rate(env: Env) -> u64 {
-        env.storage().instance().get(&DataKey::PesoExchangeRate).unwrap_or(180000)
+    #[test]
+    fn test_fresh_price_feed_keeps_donations_open() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 1_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+
+        let feed_id = env.register(MockPriceFeed, ());
+        let feed = MockPriceFeedClient::new(&env, &feed_id);
+        let xlm = Asset::Other(Symbol::new(&env, "XLM"));
+        let mxn = Asset::Other(Symbol::new(&env, "MXN"));
+        client.set_price_feed(&PriceFeedConfig { feed: feed_id, xlm_asset: xlm.clone(), mxn_asset: mxn.clone(), max_age: 60 * 60 });
+
+        // The stored rate is two days old, but the feed keeps the rate current
+        env.ledger().with_mut(|li| li.timestamp += 2 * 24 * 60 * 60);
+        let now = env.ledger().timestamp();
+        feed.set_price(&xlm, &120_000, &now);
+        feed.set_price(&mxn, &6_000, &now);
+        client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None);
+
+        // Once the feed goes quiet too, donations stop
+        env.ledger().with_mut(|li| li.timestamp += 2 * 60 * 60);
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::StaleExchangeRate))
+        );
     }
 
-    /// Emergency pause campaign
-    pub fn emergency_pause_campaign(env: Env, campaign_id: BytesN<32>) -> Result<(), soroban_sdk::Error> {
-        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+    #[test]
+    fn test_donations_record_the_rate_in_effect() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
 
-        campaign.funds_locked = true;
-        env.storage().persistent().set(&DataKey::Campaign(campaign_id), &campaign);
-        Ok(())
+        let beneficiary = Address::generate(&env);
+        let donor_a = Address::generate(&env);
+        let donor_b = Address::generate(&env);
+        fund(&env, &token, &donor_a, 100_000);
+        fund(&env, &token, &donor_b, 100_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+
+        // 98_000 net at 18 and then at 20 pesos per XLM
+        let donation_a = client.donate(&campaign_id, &donor_a, &100_000, &false, &false, &None, &None);
+        client.update_peso_exchange_rate(&200000);
+        let donation_b = client.donate(&campaign_id, &donor_b, &100_000, &false, &false, &None, &None);
+
+        let recorded_a = client.get_donation(&donation_a).unwrap();
+        let recorded_b = client.get_donation(&donation_b).unwrap();
+        assert_eq!((recorded_a.exchange_rate, recorded_a.peso_amount), (180000, 1_764_000));
+        assert_eq!((recorded_b.exchange_rate, recorded_b.peso_amount), (200000, 1_960_000));
+
+        let tx_id = client.get_donation(&donation_b).unwrap().etherfuse_tx_id.unwrap();
+        assert_eq!(client.get_etherfuse_transaction(&tx_id).unwrap().exchange_rate, 200000);
+
+        // The creation-time snapshot no longer drives peso totals
+        let campaign = client.get_campaign(&campaign_id).unwrap();
+        assert_eq!(campaign.peso_exchange_rate, 180000);
+        assert_eq!(campaign.peso_raised, 3_724_000);
+        let stats = client.get_campaign_stats(&campaign_id);
+        assert_eq!(stats.get(String::from_str(&env, "peso_raised")), Some(3_724_000));
+
+        // Refunds take the donation's own peso amount back out
+        client.start_campaign_refunds(&campaign_id);
+        client.process_refund(&donation_b);
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().peso_raised, 1_764_000);
     }
 
-    /// Resume campaign after emergency pause
-    pub fn resume_campaign(env: Env, campaign_id: BytesN<32>) -> Result<(), soroban_sdk::Error> {
-        let mut campaign: Campaign = env.storage().persistent().get(&DataKey::Campaign(campaign_id.clone()))
-            .ok_or(soroban_sdk::Error::from_contract_error(SaviaError::CampaignNotFound as u32))?;
+    #[test]
+    fn test_donation_slippage_protection() {
+        let env = Env::default();
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-        // Only resume if medical docs are verified and within deadline
-        if campaign.medical_docs_verified && env.ledger().timestamp() <= campaign.proof_deadline {
-            campaign.funds_locked = false;
-            env.storage().persistent().set(&DataKey::Campaign(campaign_id), &campaign);
-        }
-        Ok(())
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 200_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+
+        // The donor signed expecting 18 pesos per XLM: 98_000 net -> 1_764_000 pesos
+        client.update_peso_exchange_rate(&170000);
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &100_000, &false, &false, &Some(1_764_000), &None),
+            Err(Ok(SaviaError::SlippageExceeded))
+        );
+        assert_eq!(token_client.balance(&donor), 200_000);
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().current_amount, 0);
+
+        client.update_peso_exchange_rate(&180000);
+        client.donate(&campaign_id, &donor, &100_000, &false, &false, &Some(1_764_000), &None);
+
+        // A rate within the platform window can still be older than the donor accepts
+        env.ledger().with_mut(|li| li.timestamp += 10 * 60);
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &100_000, &false, &false, &None, &Some(5 * 60)),
+            Err(Ok(SaviaError::StaleExchangeRate))
+        );
+        client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &Some(15 * 60));
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().peso_raised, 3_528_000);
     }
 
-    /// Mint a new dynamic NFT for a donor (public alias called by frontend)
-    pub fn mint(env: Env, owner: Address, metadata_json: String, stage_id: u32) -> Result<BytesN<32>, soroban_sdk::Error> {
-        let counter: u64 = env.storage().instance().get(&DataKey::NFTCounter).unwrap_or(0);
-        let new_counter = counter + 1;
-        env.storage().instance().set(&DataKey::NFTCounter, &new_counter);
+    #[test]
+    fn test_peso_math_rejects_overflowing_amounts() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
 
-        let mut hash_input = Bytes::new(&env);
-        hash_input.append(&owner.clone().to_xdr(&env));
-        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
-        let nft_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
 
-        let growth_stage = match stage_id {
-            0 => TreeGrowthStage::PreSeed,
-            1 => TreeGrowthStage::Seed,
-            2 => TreeGrowthStage::Sprout,
-            3 => TreeGrowthStage::Bloom,
-            4 => TreeGrowthStage::Blossom,
-            5 => TreeGrowthStage::Tree,
-            _ => TreeGrowthStage::SaviaTree,
-        };
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &u64::MAX, &false, &false, &None, &None),
+            Err(Ok(SaviaError::InvalidAmount))
+        );
+        assert_eq!(
+            client.try_create_campaign(
+                &beneficiary,
+                &String::from_str(&env, "Tratamiento"),
+                &String::from_str(&env, "Ayuda médica"),
+                &String::from_str(&env, "Cirugía"),
+                &u64::MAX,
+                &60,
+                &String::from_str(&env, "Salud"),
+                &String::from_str(&env, "Mexico City"),
+                &String::from_str(&env, "ETF_ACCOUNT_123"),
+            ),
+            Err(Ok(SaviaError::InvalidAmount))
+        );
+        assert!(client.try_get_campaign_stats(&campaign_id).is_ok());
+    }
 
-        let current_time = env.ledger().timestamp();
-        let zero: [u8; 32] = [0u8; 32];
-        let nft = DynamicNFT {
-            id: nft_id.clone(),
-            owner: owner.clone(),
-            campaign_id: BytesN::from_array(&env, &zero),
-            tree_level: stage_id,
-            total_donated: 0,
-            donation_count: 0,
-            created_at: current_time,
-            last_updated: current_time,
-            metadata_uri: metadata_json,
-            growth_stage,
-            special_achievements: Vec::new(&env),
-        };
+    #[test]
+    fn test_etherfuse_operator_settles_conversions() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-        env.storage().persistent().set(&DataKey::DynamicNFT(nft_id.clone()), &nft);
+        let beneficiary = Address::generate(&env);
+        let donor_a = Address::generate(&env);
+        let donor_b = Address::generate(&env);
+        let operator = Address::generate(&env);
+        fund(&env, &token, &donor_a, 100_000);
+        fund(&env, &token, &donor_b, 100_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+
+        let donation_a = client.donate(&campaign_id, &donor_a, &100_000, &false, &false, &None, &None);
+        let donation_b = client.donate(&campaign_id, &donor_b, &100_000, &false, &false, &None, &None);
+        let tx_a = client.get_donation(&donation_a).unwrap().etherfuse_tx_id.unwrap();
+        let tx_b = client.get_donation(&donation_b).unwrap().etherfuse_tx_id.unwrap();
+        let external_id = String::from_str(&env, "EF-2024-000123");
+
+        // Only registered operators can settle
+        assert_eq!(
+            client.try_confirm_etherfuse_transaction(&operator, &tx_a, &external_id),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
+        client.add_etherfuse_operator(&operator);
+
+        client.confirm_etherfuse_transaction(&operator, &tx_a, &external_id);
+        let event: ConversionSettledEvent = find_event(&env, &contract_id, "etherfuse", "confirmed").unwrap();
+        assert_eq!(event.donation_id, donation_a);
+        let confirmed = client.get_etherfuse_transaction(&tx_a).unwrap();
+        assert_eq!(confirmed.status, TransactionStatus::Confirmed);
+        assert_eq!(confirmed.etherfuse_tx_id, external_id);
+
+        // Settled conversions cannot settle again
+        assert_eq!(
+            client.try_fail_etherfuse_transaction(&operator, &tx_a, &external_id),
+            Err(Ok(SaviaError::InvalidTransactionStatus))
+        );
 
-        let mut owner_nfts: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::OwnerNFTs(owner.clone())).unwrap_or(Vec::new(&env));
-        owner_nfts.push_back(nft_id.clone());
-        env.storage().persistent().set(&DataKey::OwnerNFTs(owner), &owner_nfts);
+        // A failed conversion hands the donation back
+        assert_eq!(client.fail_etherfuse_transaction(&operator, &tx_b, &String::from_str(&env, "EF-2024-000124")), 98_000);
+        assert_eq!(token_client.balance(&donor_b), 98_000);
+        assert_eq!(client.get_etherfuse_transaction(&tx_b).unwrap().status, TransactionStatus::Failed);
+        assert!(client.get_donation(&donation_b).unwrap().refunded);
+        let campaign = client.get_campaign(&campaign_id).unwrap();
+        assert_eq!(campaign.current_amount, 98_000);
+        assert_eq!(campaign.peso_raised, 1_764_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 98_000);
+        assert_eq!(client.get_trust_score(&donor_b).unwrap().donation_count, 0);
+        assert_eq!(
+            client.try_confirm_etherfuse_transaction(&operator, &tx_b, &external_id),
+            Err(Ok(SaviaError::InvalidTransactionStatus))
+        );
 
-        Ok(nft_id)
+        // Confirmed conversions can still be refunded; failed ones pay nothing more
+        client.start_campaign_refunds(&campaign_id);
+        assert_eq!(client.process_refund(&donation_b), 0);
+        assert_eq!(client.process_refund(&donation_a), 98_000);
+        assert_eq!(client.get_etherfuse_transaction(&tx_a).unwrap().status, TransactionStatus::Refunded);
     }
 
-    /// Record an on-chain donation entry
-    pub fn record_donation(env: Env, campaign_id: BytesN<32>, donor: Address, amount: u64) -> Result<BytesN<32>, soroban_sdk::Error> {
-        let counter: u64 = env.storage().instance().get(&DataKey::DonationCounter).unwrap_or(0);
-        let new_counter = counter + 1;
-        env.storage().instance().set(&DataKey::DonationCounter, &new_counter);
-
-        let mut hash_input = Bytes::new(&env);
-        hash_input.append(&Bytes::from_slice(&env, campaign_id.to_array().as_slice()));
-        hash_input.append(&donor.clone().to_xdr(&env));
-        hash_input.append(&Bytes::from_slice(&env, &amount.to_be_bytes()));
-        hash_input.append(&Bytes::from_slice(&env, &new_counter.to_be_bytes()));
-        let donation_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+    #[test]
+    fn test_failed_conversions_reverse_a_pro_rata_share() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-        let donation = Donation {
-            id: donation_id.clone(),
-            campaign_id,
-            donor,
-            amount,
-            peso_amount: 0,
-            timestamp: env.ledger().timestamp(),
-            nft_minted: false,
-            anonymous: false,
-            refunded: false,
-            etherfuse_tx_id: String::from_str(&env, ""),
-        };
+        let beneficiary = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        let operator = Address::generate(&env);
+        client.add_medical_verifier(&verifier);
+        client.add_etherfuse_operator(&operator);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let milestone = client.add_milestone(&campaign_id, &147_000, &Vec::from_array(&env, [MedicalDocType::TreatmentPlan]));
+
+        let donors = [Address::generate(&env), Address::generate(&env), Address::generate(&env)];
+        let mut donations = Vec::new(&env);
+        for donor in donors.iter() {
+            fund(&env, &token, donor, 100_000);
+            donations.push_back(client.donate(&campaign_id, donor, &100_000, &false, &false, &None, &None));
+        }
+        let tx_of = |i: u32| client.get_donation(&donations.get(i).unwrap()).unwrap().etherfuse_tx_id.unwrap();
+        let external_id = String::from_str(&env, "EF-2024-000200");
 
-        env.storage().persistent().set(&DataKey::Donation(donation_id.clone()), &donation);
-        Ok(donation_id)
+        // Half of the escrow goes out against a verified plan
+        let plan = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::TreatmentPlan,
+            &String::from_str(&env, "https://example.com/plan.pdf"),
+            &String::from_str(&env, "Plan de tratamiento"),
+        );
+        client.verify_medical_documentation(&plan, &verifier, &true);
+        client.release_milestone(&campaign_id, &milestone);
+
+        // A failure takes its share of what is left, not the whole donation
+        assert_eq!(client.fail_etherfuse_transaction(&operator, &tx_of(0), &external_id), 49_000);
+        assert_eq!(token_client.balance(&donors[0]), 49_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 98_000);
+
+        // During refunds a failure pays the same share as a refund claim
+        client.start_campaign_refunds(&campaign_id);
+        assert_eq!(client.fail_etherfuse_transaction(&operator, &tx_of(1), &external_id), 49_000);
+        assert_eq!(client.process_refund(&donations.get(2).unwrap()), 49_000);
+        assert_eq!(token_client.balance(&donors[1]), 49_000);
+        assert_eq!(token_client.balance(&donors[2]), 49_000);
+        assert_eq!(client.get_campaign_escrow(&campaign_id), 0);
     }
 
-    /// Return the number of NFTs owned by an address
-    pub fn balance_of(env: Env, owner: Address) -> u32 {
-        let owner_nfts: Vec<BytesN<32>> = env.storage().persistent().get(&DataKey::OwnerNFTs(owner)).unwrap_or(Vec::new(&env));
-        owner_nfts.len() as u32
+    #[test]
+    fn test_conversion_ids_are_unique_and_queryable() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        fund(&env, &token, &donor, 300_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+
+        // Same donor, amount and ledger
+        let first = client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None);
+        let second = client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None);
+
+        let first_tx = client.get_donation(&first).unwrap().etherfuse_tx_id.unwrap();
+        let second_tx = client.get_donation(&second).unwrap().etherfuse_tx_id.unwrap();
+        assert_ne!(first_tx, second_tx);
+
+        let conversion = client.get_donation_conversion(&second).unwrap();
+        assert_eq!(conversion.id, second_tx);
+        assert_eq!(conversion.donation_id, second);
+        assert_eq!(conversion.status, TransactionStatus::Pending);
+
+        assert_eq!(client.get_campaign_conversion_count(&campaign_id), 2);
+        let page = client.list_campaign_conversions(&campaign_id, &1, &10);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page.get(0).unwrap().id, second_tx);
+
+        // Donations recorded without a conversion have nothing to look up
+        let recorded = client.record_donation(&campaign_id, &donor, &1_000);
+        assert_eq!(client.get_donation(&recorded).unwrap().etherfuse_tx_id, None);
+        assert!(client.get_donation_conversion(&recorded).is_none());
     }
 
-    /// Return the list of NFT IDs owned by an address
-    pub fn tokens_for_owner(env: Env, owner: Address) -> Vec<BytesN<32>> {
-        env.storage().persistent().get(&DataKey::OwnerNFTs(owner)).unwrap_or(Vec::new(&env))
+    #[test]
+    fn test_clabe_validation() {
+        let env = Env::default();
+        assert!(is_valid_clabe(&String::from_str(&env, "032180000118359719")));
+        assert!(!is_valid_clabe(&String::from_str(&env, "032180000118359718"))); // Wrong check digit
+        assert!(!is_valid_clabe(&String::from_str(&env, "03218000011835971"))); // Too short
+        assert!(!is_valid_clabe(&String::from_str(&env, "03218000011835971A")));
     }
-}
 
-// ========== TESTS ==========
+    #[test]
+    fn test_payouts_of_released_funds() {
+        let env = Env::default();
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (_, token) = setup(&env, &client);
+        let token_client = TokenClient::new(&env, &token);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use soroban_sdk::{testutils::Address as _, Address, Env};
+        let beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        let operator = Address::generate(&env);
+        client.add_medical_verifier(&verifier);
+        client.add_etherfuse_operator(&operator);
+        fund(&env, &token, &donor, 100_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let milestone = client.add_milestone(&campaign_id, &50_000, &Vec::from_array(&env, [MedicalDocType::TreatmentPlan]));
+        client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None);
+
+        let clabe = String::from_str(&env, "032180000118359719");
+
+        // Nothing has been released yet
+        assert_eq!(
+            client.try_request_payout(&campaign_id, &10_000, &clabe),
+            Err(Ok(SaviaError::InsufficientFunds))
+        );
+
+        let plan = client.submit_medical_documentation(
+            &campaign_id,
+            &MedicalDocType::TreatmentPlan,
+            &String::from_str(&env, "https://example.com/plan.pdf"),
+            &String::from_str(&env, "Plan de tratamiento"),
+        );
+        client.verify_medical_documentation(&plan, &verifier, &true);
+        client.release_milestone(&campaign_id, &milestone);
+
+        assert_eq!(
+            client.try_request_payout(&campaign_id, &10_000, &String::from_str(&env, "032180000118359718")),
+            Err(Ok(SaviaError::InvalidCLABE))
+        );
+
+        let first = client.request_payout(&campaign_id, &30_000, &clabe);
+        assert_eq!(token_client.balance(&beneficiary), 20_000);
+        let payout = client.get_payout(&first).unwrap();
+        assert_eq!(payout.status, PayoutStatus::Requested);
+        assert_eq!(payout.peso_amount, 540_000);
+
+        // Payouts cannot exceed what was released
+        assert_eq!(
+            client.try_request_payout(&campaign_id, &30_000, &clabe),
+            Err(Ok(SaviaError::InsufficientFunds))
+        );
+        let second = client.request_payout(&campaign_id, &20_000, &clabe);
+
+        // Only anchor operators settle payouts
+        let external_id = String::from_str(&env, "SPEI-0001");
+        assert_eq!(
+            client.try_settle_payout(&donor, &first, &external_id),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
+        client.settle_payout(&operator, &first, &external_id);
+        assert_eq!(token_client.balance(&operator), 30_000);
+        let settled = client.get_payout(&first).unwrap();
+        assert_eq!(settled.status, PayoutStatus::Settled);
+        assert_eq!(settled.external_id, external_id);
+        assert_eq!(
+            client.try_fail_payout(&operator, &first, &external_id),
+            Err(Ok(SaviaError::InvalidPayoutStatus))
+        );
 
-    fn setup(env: &Env, client: &SaviaContractClient) {
-        client.initialize(&200, &String::from_str(env, "etherfuse_config"), &180000);
+        // A failed payout hands the tokens back and frees the amount again
+        client.fail_payout(&operator, &second, &String::from_str(&env, "SPEI-0002"));
+        let event: PayoutEvent = find_event(&env, &contract_id, "payout", "failed").unwrap();
+        assert_eq!(event, PayoutEvent { payout_id: second, amount: 20_000, status: PayoutStatus::Failed });
+        assert_eq!(token_client.balance(&beneficiary), 20_000);
+        assert_eq!(client.get_campaign(&campaign_id).unwrap().payout_amount, 30_000);
+        assert_eq!(client.list_campaign_payouts(&campaign_id, &0, &10).len(), 2);
     }
 
-    fn register_beneficiary(env: &Env, client: &SaviaContractClient, addr: &Address) {
+    #[test]
+    fn test_curp_validation() {
+        let env = Env::default();
+        let valid = [
+            "HEGG560427MVZRRL04", // RENAPO's published example
+            "GOMC900101HDFRRL07",
+            "LOPA040229MJCPRNA4", // Leap day, born 2000 or later
+            "PEXJ881231HNENRN09", // Born abroad
+        ];
+        for curp in valid {
+            assert!(is_valid_curp(&String::from_str(&env, curp)), "{}", curp);
+        }
+
+        let invalid = [
+            "ABCD123456HDFGHI01", // Not a CURP at all
+            "GOMC900101HDFRRL08", // Wrong check digit
+            "GOMC900101HDFRRL0",  // Too short
+            "GAMC900101HDFRRL07", // Second letter must be a vowel
+            "GOMC901301HDFRRL03", // Month 13
+            "MAMX000229HDFRRL07", // 1900 was not a leap year
+            "GOMC900101QDFRRL07", // Unknown sex marker
+            "GOMC900101HZZRRL00", // Unknown state
+            "GOMC900101HDFARL07", // Vowel among the internal consonants
+            "gomc900101hdfrrl07", // Lower case
+        ];
+        for curp in invalid {
+            assert!(!is_valid_curp(&String::from_str(&env, curp)), "{}", curp);
+        }
+
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        setup(&env, &client);
+        let verifier = Address::generate(&env);
+        client.add_kyc_verifier(&verifier);
+        let entity = Address::generate(&env);
         client.register_kyc(
-            addr,
-            &String::from_str(env, "ABCD123456HDFGHI01"),
-            &String::from_str(env, "Maria González"),
-            &String::from_str(env, "5551234567"),
-            &String::from_str(env, "maria@example.com"),
-            &String::from_str(env, "Guadalajara"),
+            &entity,
+            &commit(&env, KYCField::CURP, "ABCD123456HDFGHI01"),
+            &commit(&env, KYCField::FullName, "María González"),
+            &commit(&env, KYCField::Phone, "5551234567"),
+            &commit(&env, KYCField::Email, "maria@example.com"),
+            &commit(&env, KYCField::Address, "CDMX"),
             &None,
             &None,
         );
+
+        // Registration only sees the commitment, so the CURP rules apply when a
+        // verifier opens it: off-chain before attesting, or here on reveal
+        assert_eq!(client.get_kyc_record(&entity).unwrap().verification_level, KYCLevel::Unverified);
+        assert_eq!(
+            client.try_prove_kyc_field(
+                &verifier,
+                &entity,
+                &KYCField::CURP,
+                &String::from_str(&env, "ABCD123456HDFGHI01"),
+                &salt(&env, KYCField::CURP),
+            ),
+            Err(Ok(SaviaError::InvalidCURP))
+        );
     }
 
     #[test]
-    fn test_initialize_enhanced_contract() {
+    fn test_phone_normalization() {
         let env = Env::default();
-        let contract_id = env.register(SaviaContract, ());
+        let canonical = [
+            ("5551234567", "5551234567"),
+            ("+525551234567", "5551234567"),
+            ("525551234567", "5551234567"),
+            ("0445551234567", "5551234567"),
+            ("0453312345678", "3312345678"),
+            ("2221234567", "2221234567"), // 3-digit area code (Puebla)
+        ];
+        for (input, expected) in canonical {
+            assert_eq!(
+                normalize_mx_phone(&env, &String::from_str(&env, input)),
+                Some(String::from_str(&env, expected)),
+                "{}",
+                input
+            );
+        }
+
+        let invalid = [
+            "555123456",      // Too short
+            "55512345a7",     // Letters
+            "+15551234567",   // Foreign country code
+            "0465551234567",  // Unknown prefix
+            "1551234567",     // Area codes never start with 1
+            "5501234567",     // Local number after a 2-digit area code starts with 0
+            "2220123456",     // Local number after a 3-digit area code starts with 0
+            "+52 5551234567", // Separators
+        ];
+        for input in invalid {
+            assert_eq!(normalize_mx_phone(&env, &String::from_str(&env, input)), None, "{}", input);
+        }
+
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
         let client = SaviaContractClient::new(&env, &contract_id);
         setup(&env, &client);
+        let verifier = Address::generate(&env);
+        client.add_kyc_verifier(&verifier);
+        let entity = Address::generate(&env);
+        client.register_kyc(
+            &entity,
+            &commit(&env, KYCField::CURP, "GOMC900101HDFRRL07"),
+            &commit(&env, KYCField::FullName, "María González"),
+            &commit(&env, KYCField::Phone, "5551234567"),
+            &commit(&env, KYCField::Email, "maria@example.com"),
+            &commit(&env, KYCField::Address, "CDMX"),
+            &None,
+            &None,
+        );
+
+        // Registration cannot see the number; it is normalized when a verifier
+        // opens it, and any accepted form proves the canonical commitment
+        let phone_salt = salt(&env, KYCField::Phone);
+        client.prove_kyc_field(&verifier, &entity, &KYCField::Phone, &String::from_str(&env, "+525551234567"), &phone_salt);
+        assert_eq!(client.get_kyc_record(&entity).unwrap().proven_fields, Vec::from_array(&env, [KYCField::Phone]));
+        assert_eq!(
+            client.try_prove_kyc_field(&verifier, &entity, &KYCField::Phone, &String::from_str(&env, "55512345a7"), &phone_salt),
+            Err(Ok(SaviaError::InvalidPhoneNumber))
+        );
     }
 
     #[test]
-    fn test_kyc_registration() {
+    fn test_kyc_levels_are_attested_by_verifiers() {
         let env = Env::default();
-        let contract_id = env.register(SaviaContract, ());
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
         let client = SaviaContractClient::new(&env, &contract_id);
         setup(&env, &client);
 
-        let user = Address::generate(&env);
+        let doctor = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        let outsider = Address::generate(&env);
+        client.add_kyc_verifier(&verifier);
+
+        // Claiming medical credentials no longer grants a level by itself
         client.register_kyc(
-            &user,
-            &String::from_str(&env, "ABCD123456HDFGHI01"),
-            &String::from_str(&env, "Juan Pérez"),
-            &String::from_str(&env, "5551234567"),
-            &String::from_str(&env, "juan@example.com"),
-            &String::from_str(&env, "Mexico City"),
-            &None,
-            &None,
+            &doctor,
+            &commit(&env, KYCField::CURP, "HEGG560427MVZRRL04"),
+            &commit(&env, KYCField::FullName, "Gloria Hernández"),
+            &commit(&env, KYCField::Phone, "2291234567"),
+            &commit(&env, KYCField::Email, "gloria@example.com"),
+            &commit(&env, KYCField::Address, "Veracruz"),
+            &Some(String::from_str(&env, "CED-1234567")),
+            &Some(String::from_str(&env, "Hospital General")),
+        );
+        let kyc_event: KYCRegisteredEvent = find_event(&env, &contract_id, "kyc", "register").unwrap();
+        assert_eq!(kyc_event.verification_level, KYCLevel::Unverified);
+        let record = client.get_kyc_record(&doctor).unwrap();
+        assert_eq!(record.verification_level, KYCLevel::Unverified);
+        assert_eq!(record.verified_by, None);
+
+        // Unverified beneficiaries cannot raise funds
+        assert_eq!(
+            client.try_create_campaign(
+                &doctor,
+                &String::from_str(&env, "Tratamiento"),
+                &String::from_str(&env, "Ayuda médica"),
+                &String::from_str(&env, "Cirugía"),
+                &500000,
+                &60,
+                &String::from_str(&env, "Salud"),
+                &String::from_str(&env, "Veracruz"),
+                &String::from_str(&env, "ETF_ACCOUNT_123"),
+            ),
+            Err(Ok(SaviaError::KYCNotVerified))
         );
 
-        let kyc_record = client.get_kyc_record(&user);
-        assert!(kyc_record.is_some());
+        assert_eq!(
+            client.try_verify_kyc(&outsider, &doctor, &KYCLevel::FullyVerified),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
+        assert_eq!(
+            client.try_verify_kyc(&verifier, &doctor, &KYCLevel::Unverified),
+            Err(Ok(SaviaError::InvalidKYCLevel))
+        );
+        assert_eq!(
+            client.try_verify_kyc(&verifier, &outsider, &KYCLevel::BasicVerified),
+            Err(Ok(SaviaError::KYCNotFound))
+        );
+
+        // No level is granted until the CURP and phone have been proven
+        client.prove_kyc_field(&verifier, &doctor, &KYCField::CURP, &String::from_str(&env, "HEGG560427MVZRRL04"), &salt(&env, KYCField::CURP));
+        assert_eq!(
+            client.try_verify_kyc(&verifier, &doctor, &KYCLevel::FullyVerified),
+            Err(Ok(SaviaError::KYCFieldsNotProven))
+        );
+        assert_eq!(
+            client.try_prove_kyc_field(&outsider, &doctor, &KYCField::Phone, &String::from_str(&env, "2291234567"), &salt(&env, KYCField::Phone)),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
+        client.prove_kyc_field(&verifier, &doctor, &KYCField::Phone, &String::from_str(&env, "2291234567"), &salt(&env, KYCField::Phone));
+        let proven: KYCFieldProvenEvent = find_event(&env, &contract_id, "kyc", "proven").unwrap();
+        assert_eq!(proven, KYCFieldProvenEvent { verifier: verifier.clone(), field: KYCField::Phone });
+
+        client.verify_kyc(&verifier, &doctor, &KYCLevel::MedicalVerified);
+        let record = client.get_kyc_record(&doctor).unwrap();
+        assert_eq!(record.verification_level, KYCLevel::MedicalVerified);
+        assert_eq!(record.verified_by, Some(verifier.clone()));
+        assert_eq!(record.verified_at, env.ledger().timestamp());
+        assert_eq!(record.expires_at, env.ledger().timestamp() + 365 * 24 * 60 * 60);
+
+        // Medical verification needs credentials on file
+        let patient = Address::generate(&env);
+        register_beneficiary(&env, &client, &patient);
+        assert_eq!(
+            client.try_verify_kyc(&verifier, &patient, &KYCLevel::MedicalVerified),
+            Err(Ok(SaviaError::InvalidKYCLevel))
+        );
     }
 
     #[test]
-    fn test_enhanced_campaign_creation() {
+    fn test_kyc_renewal_and_revocation_lock_campaigns() {
         let env = Env::default();
-        let contract_id = env.register(SaviaContract, ());
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
         let client = SaviaContractClient::new(&env, &contract_id);
-        setup(&env, &client);
+        let (_, token) = setup(&env, &client);
 
         let beneficiary = Address::generate(&env);
+        let other = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        client.add_kyc_verifier(&verifier);
+        fund(&env, &token, &donor, 1_000_000);
         register_beneficiary(&env, &client, &beneficiary);
+        register_beneficiary(&env, &client, &other);
+
+        // Renewal extends the record from the day it is renewed
+        env.ledger().with_mut(|li| li.timestamp += 310 * 24 * 60 * 60);
+        client.renew_kyc(&verifier, &beneficiary, &KYCReason::PeriodicReview);
+        let record = client.get_kyc_record(&beneficiary).unwrap();
+        assert_eq!(record.expires_at, env.ledger().timestamp() + 365 * 24 * 60 * 60);
+        assert_eq!(record.verified_by, Some(verifier.clone()));
+
+        let first = create_test_campaign(&env, &client, &beneficiary);
+        let second = create_test_campaign(&env, &client, &beneficiary);
+        let unrelated = create_test_campaign(&env, &client, &other);
+        assert_eq!(client.get_beneficiary_campaigns(&beneficiary), Vec::from_array(&env, [first.clone(), second.clone()]));
+
+        // The other beneficiary lets theirs lapse while the campaign runs:
+        // donations stop and a keeper locks the campaign
+        env.ledger().with_mut(|li| li.timestamp += 56 * 24 * 60 * 60);
+        client.update_peso_exchange_rate(&180000);
+        assert_eq!(
+            client.try_donate(&unrelated, &donor, &10_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::KYCExpired))
+        );
+        assert_eq!(client.check_kyc_status(&beneficiary), 0);
+        assert_eq!(client.check_kyc_status(&other), 1);
+        assert!(client.get_campaign(&unrelated).unwrap().funds_locked);
+        client.donate(&first, &donor, &10_000, &false, &false, &None, &None);
+
+        // Revocation locks every open campaign of the holder at once
+        assert_eq!(client.revoke_kyc(&verifier, &beneficiary, &KYCReason::FraudSuspected), 2);
+        let event: KYCRevokedEvent = find_event(&env, &contract_id, "kyc", "revoked").unwrap();
+        assert_eq!(event.reason, KYCReason::FraudSuspected);
+        assert!(client.get_campaign(&first).unwrap().funds_locked);
+        assert!(client.get_campaign(&second).unwrap().funds_locked);
+        assert_eq!(client.get_kyc_record(&beneficiary).unwrap().verification_level, KYCLevel::Unverified);
+
+        // The beneficiary cannot lift a KYC lock by submitting documents
+        let milestone = client.add_milestone(&second, &5_000, &Vec::from_array(&env, [MedicalDocType::TreatmentPlan]));
+        client.submit_medical_documentation(
+            &second,
+            &MedicalDocType::TreatmentPlan,
+            &String::from_str(&env, "https://example.com/plan.pdf"),
+            &String::from_str(&env, "Plan de tratamiento"),
+        );
+        let campaign = client.get_campaign(&second).unwrap();
+        assert!(campaign.funds_locked);
+        assert_eq!(campaign.lock_reason, LockReason::KYCLapsed);
+        assert_eq!(
+            client.try_release_milestone(&second, &milestone),
+            Err(Ok(SaviaError::FundsLocked))
+        );
 
-        let campaign_id = client.create_campaign(
-            &beneficiary,
-            &String::from_str(&env, "Tratamiento de Cáncer"),
-            &String::from_str(&env, "Necesito ayuda para mi tratamiento"),
-            &String::from_str(&env, "Cáncer de mama"),
-            &500000,
-            &60,
-            &String::from_str(&env, "Salud"),
-            &String::from_str(&env, "Guadalajara"),
-            &String::from_str(&env, "ETF_ACCOUNT_123"),
+        assert_eq!(
+            client.try_renew_kyc(&verifier, &beneficiary, &KYCReason::DocumentsUpdated),
+            Err(Ok(SaviaError::KYCNotVerified))
         );
 
-        let campaign = client.get_campaign(&campaign_id);
-        assert!(campaign.is_some());
-        assert!(campaign.unwrap().kyc_verified);
+        let history = client.get_kyc_history(&beneficiary);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.get(1).unwrap().action, KYCAction::Renewed);
+        let revoked = history.get(2).unwrap();
+        assert_eq!((revoked.action, revoked.reason, revoked.verifier), (KYCAction::Revoked, KYCReason::FraudSuspected, verifier.clone()));
+
+        // The admin cannot resume a campaign that lacks verified proof, and
+        // renewing the lapsed KYC lifts the lock it caused
+        assert_eq!(client.try_resume_campaign(&unrelated), Err(Ok(SaviaError::NotApproved)));
+        client.renew_kyc(&verifier, &other, &KYCReason::PeriodicReview);
+        assert_eq!(find_event::<Address>(&env, &contract_id, "campaign", "resumed"), Some(verifier));
+        let campaign = client.get_campaign(&unrelated).unwrap();
+        assert!(!campaign.funds_locked);
+        assert_eq!(campaign.lock_reason, LockReason::Unlocked);
+        client.donate(&unrelated, &donor, &10_000, &false, &false, &None, &None);
     }
 
     #[test]
-    fn test_donation_with_peso_conversion() {
+    fn test_kyc_stores_only_commitments() {
         let env = Env::default();
-        let contract_id = env.register(SaviaContract, ());
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
         let client = SaviaContractClient::new(&env, &contract_id);
         setup(&env, &client);
 
-        let beneficiary = Address::generate(&env);
-        let donor = Address::generate(&env);
-
-        register_beneficiary(&env, &client, &beneficiary);
+        let holder = Address::generate(&env);
+        let verifier = Address::generate(&env);
+        client.add_kyc_verifier(&verifier);
+        register_beneficiary(&env, &client, &holder);
+
+        let record = client.get_kyc_record(&holder).unwrap();
+        assert_eq!(record.curp_hash, commit(&env, KYCField::CURP, "GOMC900101HDFRRL07"));
+        assert_ne!(record.curp_hash, record.full_name_hash);
+        assert_eq!(record.proven_fields, Vec::from_array(&env, [KYCField::CURP, KYCField::Phone]));
+        assert!(record.revealed_salts.is_empty());
+
+        // Attestations are bound to the commitment on file for that field
+        client.attest_kyc_field(&verifier, &holder, &KYCField::Phone, &record.phone_hash);
+        let attested: KYCFieldProvenEvent = find_event(&env, &contract_id, "kyc", "attested").unwrap();
+        assert_eq!(attested, KYCFieldProvenEvent { verifier: verifier.clone(), field: KYCField::Phone });
+        assert_eq!(
+            client.try_attest_kyc_field(&verifier, &holder, &KYCField::FullName, &record.curp_hash),
+            Err(Ok(SaviaError::KYCProofMismatch))
+        );
+        assert_eq!(
+            client.try_attest_kyc_field(&Address::generate(&env), &holder, &KYCField::FullName, &record.full_name_hash),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
 
-        let campaign_id = client.create_campaign(
-            &beneficiary,
-            &String::from_str(&env, "Tratamiento Médico"),
-            &String::from_str(&env, "Ayuda médica urgente"),
-            &String::from_str(&env, "Cirugía"),
-            &500000,
-            &60,
-            &String::from_str(&env, "Salud"),
-            &String::from_str(&env, "Mexico City"),
-            &String::from_str(&env, "ETF_ACCOUNT_123"),
+        let name_salt = salt(&env, KYCField::FullName);
+        let email_salt = salt(&env, KYCField::Email);
+        client.prove_kyc_field(&verifier, &holder, &KYCField::FullName, &String::from_str(&env, "Maria González"), &name_salt);
+        client.prove_kyc_field(&verifier, &holder, &KYCField::Email, &String::from_str(&env, "maria@example.com"), &email_salt);
+        client.prove_kyc_field(&verifier, &holder, &KYCField::Address, &String::from_str(&env, "Guadalajara"), &salt(&env, KYCField::Address));
+        assert_eq!(client.get_kyc_record(&holder).unwrap().proven_fields.len(), 5);
+
+        // Wrong value, wrong salt, or a value committed under another field
+        assert_eq!(
+            client.try_prove_kyc_field(&verifier, &holder, &KYCField::FullName, &String::from_str(&env, "Mario González"), &name_salt),
+            Err(Ok(SaviaError::KYCProofMismatch))
+        );
+        assert_eq!(
+            client.try_prove_kyc_field(
+                &verifier,
+                &holder,
+                &KYCField::Email,
+                &String::from_str(&env, "maria@example.com"),
+                &BytesN::from_array(&env, &[8u8; 32]),
+            ),
+            Err(Ok(SaviaError::KYCProofMismatch))
+        );
+        assert_eq!(
+            client.try_prove_kyc_field(&verifier, &holder, &KYCField::Email, &String::from_str(&env, "Guadalajara"), &email_salt),
+            Err(Ok(SaviaError::KYCProofMismatch))
+        );
+        assert_eq!(
+            client.try_prove_kyc_field(&verifier, &Address::generate(&env), &KYCField::Email, &String::from_str(&env, "x"), &email_salt),
+            Err(Ok(SaviaError::KYCNotFound))
         );
 
-        client.donate(
-            &campaign_id,
-            &donor,
-            &10000000,
-            &false,
-            &true,
+        // A holder who reused one salt across fields
+        let careless = Address::generate(&env);
+        let shared = BytesN::from_array(&env, &[9u8; 32]);
+        client.register_kyc(
+            &careless,
+            &commit(&env, KYCField::CURP, "GOMC900101HDFRRL07"),
+            &commit_with_salt(&env, KYCField::FullName, &shared, "Ana López"),
+            &commit(&env, KYCField::Phone, "5551234567"),
+            &commit_with_salt(&env, KYCField::Email, &shared, "ana@example.com"),
+            &commit_with_salt(&env, KYCField::FullName, &shared, "Ana López"),
+            &None,
+            &None,
         );
+        client.prove_kyc_field(&verifier, &careless, &KYCField::FullName, &String::from_str(&env, "Ana López"), &shared);
 
-        let campaigns = client.get_campaign(&campaign_id);
-        assert!(campaigns.is_some());
-        assert!(campaigns.unwrap().current_amount > 0);
+        // The field tag stops a commitment from opening as another field
+        assert_eq!(
+            client.try_prove_kyc_field(&verifier, &careless, &KYCField::Address, &String::from_str(&env, "Ana López"), &shared),
+            Err(Ok(SaviaError::KYCProofMismatch))
+        );
+        // A salt made public by one proof cannot open a second field
+        assert_eq!(
+            client.try_prove_kyc_field(&verifier, &careless, &KYCField::Email, &String::from_str(&env, "ana@example.com"), &shared),
+            Err(Ok(SaviaError::KYCSaltReused))
+        );
     }
 
     #[test]
-    fn test_dynamic_nft_growth() {
+    fn test_kyc_tier_limits() {
         let env = Env::default();
-        let contract_id = env.register(SaviaContract, ());
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
         let client = SaviaContractClient::new(&env, &contract_id);
-        setup(&env, &client);
+        let (_, token) = setup(&env, &client);
 
         let beneficiary = Address::generate(&env);
-        let donor = Address::generate(&env);
+        let anonymous_donor = Address::generate(&env);
+        let verified_donor = Address::generate(&env);
+        let officer = Address::generate(&env);
+        let outsider = Address::generate(&env);
+        fund(&env, &token, &anonymous_donor, 1_000_000);
+        fund(&env, &token, &verified_donor, 1_000_000);
         register_beneficiary(&env, &client, &beneficiary);
-
-        let campaign_id = client.create_campaign(
-            &beneficiary,
-            &String::from_str(&env, "Tratamiento"),
-            &String::from_str(&env, "Ayuda médica"),
-            &String::from_str(&env, "Cirugía"),
-            &500000,
-            &60,
-            &String::from_str(&env, "Salud"),
-            &String::from_str(&env, "Mexico City"),
-            &String::from_str(&env, "ETF_ACCOUNT_123"),
+        register_beneficiary(&env, &client, &verified_donor);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+
+        // Limits are expressed in UMA; one UMA is 100_000 in peso units
+        let config = ComplianceConfig {
+            unit: LimitUnit::Uma,
+            uma_value: 100_000,
+            verified_donor_threshold: 10,
+            period: 24 * 60 * 60,
+        };
+        assert_eq!(
+            client.try_set_compliance_config(&outsider, &config),
+            Err(Ok(SaviaError::NotAuthorized))
         );
+        client.add_compliance_officer(&officer);
+        client.set_compliance_config(&officer, &config);
+        client.set_tier_limits(&officer, &KYCLevel::Unverified, &TierLimits {
+            max_donation: 15,
+            max_period_total: 25,
+            max_campaign_goal: 0,
+        });
+
+        // 50_000 at 18 pesos is 900_000, under the 10 UMA threshold
+        client.donate(&campaign_id, &anonymous_donor, &50_000, &false, &false, &None, &None);
+        assert_eq!(client.get_donor_period_total(&anonymous_donor).total, 900_000);
+
+        // 1_008_000 pesos given needs a verified donor, even though only
+        // 987_840 reaches the campaign after the fee
+        assert_eq!(
+            client.try_donate(&campaign_id, &anonymous_donor, &56_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::KYCNotVerified))
+        );
+        client.donate(&campaign_id, &verified_donor, &100_000, &false, &false, &None, &None);
 
-        client.donate(&campaign_id, &donor, &50000000, &false, &true);
-
-        let nft = client.get_donor_nft(&donor, &campaign_id);
-        assert!(nft.is_some());
-        let nft_data = nft.unwrap();
-        assert_eq!(nft_data.growth_stage, TreeGrowthStage::PreSeed);
+        // The unverified tier allows 25 UMA per day
+        client.donate(&campaign_id, &anonymous_donor, &50_000, &false, &false, &None, &None);
+        assert_eq!(
+            client.try_donate(&campaign_id, &anonymous_donor, &50_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::DonationLimitExceeded))
+        );
+        env.ledger().with_mut(|li| li.timestamp += 24 * 60 * 60);
+        client.update_peso_exchange_rate(&180000);
+        client.donate(&campaign_id, &anonymous_donor, &50_000, &false, &false, &None, &None);
+        assert_eq!(client.get_donor_period_total(&anonymous_donor).total, 900_000);
+
+        // A 500_000 goal is 90 UMA, over the basic tier's cap
+        client.set_tier_limits(&officer, &KYCLevel::BasicVerified, &TierLimits {
+            max_donation: 0,
+            max_period_total: 0,
+            max_campaign_goal: 50,
+        });
+        assert_eq!(
+            client.try_create_campaign(
+                &beneficiary,
+                &String::from_str(&env, "Tratamiento"),
+                &String::from_str(&env, "Ayuda médica"),
+                &String::from_str(&env, "Cirugía"),
+                &500000,
+                &60,
+                &String::from_str(&env, "Salud"),
+                &String::from_str(&env, "Mexico City"),
+                &String::from_str(&env, "ETF_ACCOUNT_123"),
+            ),
+            Err(Ok(SaviaError::CampaignGoalLimitExceeded))
+        );
+    }
 
-        client.donate(&campaign_id, &donor, &150000000, &false, &true);
+    #[test]
+    fn test_aml_alerts_on_rolling_donor_totals() {
+        let env = Env::default();
+        env.ledger().with_mut(|li| li.timestamp = 1_700_000_000);
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
+        let client = SaviaContractClient::new(&env, &contract_id);
+        let (admin, token) = setup(&env, &client);
 
-        let updated_nft = client.get_donor_nft(&donor, &campaign_id);
-        assert!(updated_nft.is_some());
-        let updated_nft_data = updated_nft.unwrap();
-        assert_eq!(updated_nft_data.growth_stage, TreeGrowthStage::Seed);
-        assert_eq!(updated_nft_data.donation_count, 2);
+        let beneficiary = Address::generate(&env);
+        let other_beneficiary = Address::generate(&env);
+        let donor = Address::generate(&env);
+        let officer = Address::generate(&env);
+        fund(&env, &token, &donor, 1_000_000);
+        register_beneficiary(&env, &client, &beneficiary);
+        register_beneficiary(&env, &client, &other_beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let other_campaign = create_test_campaign(&env, &client, &beneficiary);
+        let unrelated = create_test_campaign(&env, &client, &other_beneficiary);
+
+        client.add_compliance_officer(&officer);
+        client.set_aml_config(&officer, &AmlConfig { threshold: 3_000_000, window: 7 * 24 * 60 * 60 });
+
+        // 1_800_000 gross pesos per donation, totalled per beneficiary across
+        // their campaigns; the second gift to the same beneficiary crosses
+        client.donate(&campaign_id, &donor, &100_000, &true, &false, &None, &None);
+        client.donate(&unrelated, &donor, &100_000, &false, &false, &None, &None);
+        assert_eq!(client.get_aml_alert_count(), 0);
+        let crossing = client.donate(&other_campaign, &donor, &100_000, &true, &false, &None, &None);
+        let event: AmlAlertEvent = find_event(&env, &contract_id, "aml", "alert").unwrap();
+        assert_eq!(event, AmlAlertEvent { alert_id: 1, rolling_total: 3_600_000, threshold: 3_000_000 });
+
+        // Staying above the threshold does not raise another alert
+        client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None);
+        assert_eq!(client.get_aml_alert_count(), 1);
+
+        // Once earlier donations leave the window the total can cross again
+        env.ledger().with_mut(|li| li.timestamp += 8 * 24 * 60 * 60);
+        client.update_peso_exchange_rate(&180000);
+        client.donate(&campaign_id, &donor, &100_000, &false, &false, &None, &None);
+        client.donate(&other_campaign, &donor, &100_000, &false, &false, &None, &None);
+        assert_eq!(client.get_aml_alert_count(), 2);
+
+        // Only compliance can review alerts, which name anonymous donors too
+        assert_eq!(
+            client.try_list_aml_alerts(&donor, &0, &10),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
+        let alerts = client.list_aml_alerts(&officer, &0, &10);
+        assert_eq!(alerts.len(), 2);
+        let first = alerts.get(0).unwrap();
+        assert_eq!((first.donor, first.beneficiary, first.donation_id), (donor.clone(), beneficiary, crossing));
+        assert_eq!(client.list_aml_alerts(&admin, &1, &10).get(0).unwrap().id, 2);
     }
 
     #[test]
-    fn test_medical_documentation_flow() {
+    fn test_blocklist_and_screening_hook() {
         let env = Env::default();
-        let contract_id = env.register(SaviaContract, ());
+        let contract_id = env.register(SaviaContract, (Address::generate(&env),));
         let client = SaviaContractClient::new(&env, &contract_id);
-        setup(&env, &client);
+        let (_, token) = setup(&env, &client);
 
         let beneficiary = Address::generate(&env);
-        let verifier = Address::generate(&env);
-
-        client.add_medical_verifier(&verifier);
+        let donor = Address::generate(&env);
+        let officer = Address::generate(&env);
+        let outsider = Address::generate(&env);
+        fund(&env, &token, &donor, 1_000_000);
         register_beneficiary(&env, &client, &beneficiary);
+        let campaign_id = create_test_campaign(&env, &client, &beneficiary);
+        let milestone = client.add_milestone(&campaign_id, &10_000, &Vec::from_array(&env, [MedicalDocType::TreatmentPlan]));
+        client.initialize_trust_score(&donor);
+        client.add_compliance_officer(&officer);
+
+        assert_eq!(
+            client.try_block_address(&outsider, &donor),
+            Err(Ok(SaviaError::NotAuthorized))
+        );
+        client.block_address(&officer, &donor);
+        client.block_address(&officer, &beneficiary);
+        assert!(client.is_blocked(&donor));
 
-        let campaign_id = client.create_campaign(
-            &beneficiary,
-            &String::from_str(&env, "Tratamiento"),
-            &String::from_str(&env, "Ayuda médica"),
-            &String::from_str(&env, "Cáncer"),
-            &500000,
-            &60,
-            &String::from_str(&env, "Salud"),
-            &String::from_str(&env, "Mexico City"),
-            &String::from_str(&env, "ETF_ACCOUNT_123"),
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
+        assert_eq!(
+            client.try_report_fraud(&beneficiary, &donor),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
+        assert_eq!(
+            client.try_register_kyc(
+                &donor,
+                &commit(&env, KYCField::CURP, "GOMC900101HDFRRL07"),
+                &commit(&env, KYCField::FullName, "Juan Pérez"),
+                &commit(&env, KYCField::Phone, "5551234567"),
+                &commit(&env, KYCField::Email, "juan@example.com"),
+                &commit(&env, KYCField::Address, "Mexico City"),
+                &None,
+                &None,
+            ),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
+        assert_eq!(
+            client.try_create_campaign(
+                &beneficiary,
+                &String::from_str(&env, "Tratamiento"),
+                &String::from_str(&env, "Ayuda médica"),
+                &String::from_str(&env, "Cirugía"),
+                &500000,
+                &60,
+                &String::from_str(&env, "Salud"),
+                &String::from_str(&env, "Mexico City"),
+                &String::from_str(&env, "ETF_ACCOUNT_123"),
+            ),
+            Err(Ok(SaviaError::AddressBlocked))
         );
 
-        let doc_hash = client.submit_medical_documentation(
-            &campaign_id,
-            &MedicalDocType::MedicalDiagnosis,
-            &String::from_str(&env, "https://example.com/medical-report.pdf"),
-            &String::from_str(&env, "Diagnóstico médico oficial"),
+        // A blocked beneficiary can neither receive donations nor draw funds
+        client.unblock_address(&officer, &donor);
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
+        assert_eq!(
+            client.try_release_milestone(&campaign_id, &milestone),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
+        assert_eq!(
+            client.try_request_payout(&campaign_id, &10_000, &String::from_str(&env, "032180000118359719")),
+            Err(Ok(SaviaError::AddressBlocked))
         );
 
-        client.verify_medical_documentation(&doc_hash, &verifier, &true);
+        client.unblock_address(&officer, &beneficiary);
+        client.donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None);
+
+        // Denylist screening refuses listed addresses
+        let screening_id = env.register(MockScreening, ());
+        let screening = MockScreeningClient::new(&env, &screening_id);
+        client.set_screening(&officer, &ScreeningConfig { contract: screening_id.clone(), mode: ScreeningMode::Denylist });
+        screening.set_listed(&donor, &true);
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
 
-        let campaign = client.get_campaign(&campaign_id);
-        assert!(campaign.is_some());
-        assert!(campaign.unwrap().medical_docs_verified);
+        // Allowlist screening admits only listed addresses, the beneficiary included
+        client.set_screening(&officer, &ScreeningConfig { contract: screening_id, mode: ScreeningMode::Allowlist });
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
+        screening.set_listed(&beneficiary, &true);
+        client.donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None);
+        screening.set_listed(&donor, &false);
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
+
+        // A screening contract that cannot answer fails closed
+        client.set_screening(&officer, &ScreeningConfig { contract: token, mode: ScreeningMode::Denylist });
+        assert_eq!(
+            client.try_donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None),
+            Err(Ok(SaviaError::AddressBlocked))
+        );
+
+        client.clear_screening(&officer);
+        client.donate(&campaign_id, &donor, &10_000, &false, &false, &None, &None);
     }
-}
\ No newline at end of file
+}
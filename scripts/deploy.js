@@ -14,15 +14,15 @@ async function deploy() {
     console.log('Fund at: https://laboratory.stellar.org/#account-creator?network=testnet');
     console.log('');
     console.log('Then deploy:');
-    console.log(`  soroban contract deploy --wasm ${wasmPath} --source <SECRET> --network testnet`);
+    console.log(`  soroban contract deploy --wasm ${wasmPath} --source <SECRET> --network testnet -- --admin <ADMIN_ADDRESS>`);
     return;
   }
   const kp = Keypair.fromSecret(SECRET);
   console.log('Deploying Savia contracts from:', kp.publicKey());
   // Deploy via Stellar CLI:
-  // stellar contract deploy --wasm target/wasm32v1-none/release/savia.wasm --source KEY_ALIAS --network testnet
+  // stellar contract deploy --wasm target/wasm32v1-none/release/savia.wasm --source KEY_ALIAS --network testnet -- --admin ADMIN_ADDRESS
   console.log('Run:');
-  console.log(`  stellar contract deploy --wasm ${wasmPath} --source <KEY_ALIAS> --network testnet`);
+  console.log(`  stellar contract deploy --wasm ${wasmPath} --source <KEY_ALIAS> --network testnet -- --admin <ADMIN_ADDRESS>`);
   console.log('');
   console.log('Current deployed contract ID: CBBHIK6QE6K6BBDNGOBEXDZJCEZLCLXNNEDS7IEPWFIOQE46D2VN3YL5');
 }